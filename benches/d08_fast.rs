@@ -0,0 +1,70 @@
+use std::time::{Duration, Instant};
+
+use aoc_2020::d08::fast::FastCpu;
+use aoc_2020::d08::{Cpu, Instruction, OpCode};
+
+// A long generated boot program: runs of acc separated by short forward jumps, with a
+// few nops and memory stores mixed in. It runs straight through once and halts.
+fn generate(len: usize) -> Vec<Instruction> {
+    (0..len)
+        .map(|i| match i % 16 {
+            7 => Instruction {
+                op: OpCode::Jmp,
+                val: 2,
+            },
+            8 => Instruction {
+                op: OpCode::Acc,
+                val: -99,
+            },
+            11 => Instruction {
+                op: OpCode::Nop,
+                val: 0,
+            },
+            15 => Instruction {
+                op: OpCode::St,
+                val: (i % 64) as i64,
+            },
+            n => Instruction {
+                op: OpCode::Acc,
+                val: n as i64 - 5,
+            },
+        })
+        .collect()
+}
+
+fn best_of<F: FnMut() -> i64>(runs: usize, mut f: F) -> (Duration, i64) {
+    let mut best = Duration::MAX;
+    let mut result = 0;
+    for _ in 0..runs {
+        let start = Instant::now();
+        result = f();
+        best = best.min(start.elapsed());
+    }
+    (best, result)
+}
+
+fn main() {
+    for len in &[10_000, 100_000, 1_000_000] {
+        let instructions = generate(*len);
+
+        let (interpreted, acc) = best_of(5, || {
+            let mut cpu = Cpu::new(instructions.clone());
+            cpu.run();
+            cpu.get_acc()
+        });
+        let (fast, fast_acc) = best_of(5, || {
+            let mut cpu = FastCpu::new(instructions.clone());
+            cpu.run();
+            cpu.get_acc()
+        });
+        assert_eq!(acc, fast_acc);
+
+        println!(
+            "{:>9} instructions: Cpu {:>10.3?}  FastCpu {:>10.3?}  ({:.1}x)",
+            len,
+            interpreted,
+            fast,
+            interpreted.as_secs_f64() / fast.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
+
+use aoc_2020::bench::{self, Measurement};
+use aoc_2020::fixture;
+use aoc_2020::generate::generate;
+use aoc_2020::solution::{parts, DAYS};
+
+// Times every day's generator and parts on the real input, when there is one in the input
+// directory, and on generated inputs of a typical and of the largest size. Writes days.json
+// and days.csv to the output directory.
+//
+//     cargo bench --bench days -- [day ...] [--runs <n>] [--dir <dir>] [--out <dir>]
+
+const USAGE: &str = "usage: days [day ...] [--runs <n>] [--dir <dir>] [--out <dir>]";
+
+// a part stops being repeated once its runs have taken this long
+const BUDGET: Duration = Duration::from_secs(5);
+
+struct Options {
+    days: Vec<u32>,
+    runs: usize,
+    dir: PathBuf,
+    out: PathBuf,
+}
+
+fn options() -> Result<Options, String> {
+    let mut options = Options {
+        days: Vec::new(),
+        runs: 10,
+        dir: PathBuf::from("input/2020"),
+        out: PathBuf::from("target/bench"),
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| USAGE.to_string());
+        match arg.as_str() {
+            // cargo bench passes this to every bench
+            "--bench" => {}
+            "--runs" | "-n" => {
+                options.runs = value()?
+                    .parse()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or("--runs needs a positive number")?
+            }
+            "--dir" | "-d" => options.dir = PathBuf::from(value()?),
+            "--out" | "-o" => options.out = PathBuf::from(value()?),
+            day => match day.parse() {
+                Ok(day) if DAYS.contains(&day) => options.days.push(day),
+                _ => return Err(USAGE.to_string()),
+            },
+        }
+    }
+    if options.days.is_empty() {
+        options.days = DAYS.to_vec();
+    }
+    Ok(options)
+}
+
+// the inputs for a day with their names
+fn inputs(day: u32, dir: &Path) -> Vec<(String, String)> {
+    let mut inputs = Vec::new();
+    let path = dir.join(format!("day{}.txt", day));
+    if path.exists() {
+        match fixture::read_input(&path) {
+            Ok(input) => inputs.push((path.display().to_string(), input)),
+            Err(message) => eprintln!("{}", message),
+        }
+    }
+    for (label, size) in &[("100", 100), ("largest", usize::MAX)] {
+        let generated = generate(day, 1, *size).unwrap();
+        inputs.push((format!("generated seed 1 size {}", label), generated.input));
+    }
+    inputs
+}
+
+fn main() {
+    let options = options().unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        process::exit(2);
+    });
+
+    println!(
+        "{:>3} {:>4} {:<32} {:>11} {:>11} {:>11} {:>11}",
+        "day", "part", "input", "gen median", "min", "median", "max"
+    );
+    let mut measurements: Vec<Measurement> = Vec::new();
+    for day in &options.days {
+        for (name, input) in inputs(*day, &options.dir) {
+            for part in 1..=parts(*day) {
+                let m = match bench::measure(*day, part, &name, &input, options.runs, BUDGET) {
+                    Ok(m) => m,
+                    Err(e) => {
+                        eprintln!("day {} part {} {}: {}", day, part, name, e);
+                        continue;
+                    }
+                };
+                println!(
+                    "{:>3} {:>4} {:<32} {:>11} {:>11} {:>11} {:>11}",
+                    day,
+                    part,
+                    name,
+                    format!("{:.1?}", m.generator.median),
+                    format!("{:.1?}", m.solver.min),
+                    format!("{:.1?}", m.solver.median),
+                    format!("{:.1?}", m.solver.max),
+                );
+                measurements.push(m);
+            }
+        }
+    }
+
+    let written = fs::create_dir_all(&options.out)
+        .and_then(|_| fs::write(options.out.join("days.json"), bench::to_json(&measurements)))
+        .and_then(|_| fs::write(options.out.join("days.csv"), bench::to_csv(&measurements)));
+    match written {
+        Ok(()) => println!("reports written to {}", options.out.display()),
+        Err(e) => {
+            eprintln!("can't write reports to {}: {}", options.out.display(), e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// Generates one test per line of tests/fixtures/manifest.txt, included by tests/fixtures.rs.
+// See src/fixture.rs for the manifest format.
+fn main() {
+    println!("cargo:rerun-if-changed=tests/fixtures");
+
+    let manifest = Path::new("tests/fixtures/manifest.txt");
+    let text = fs::read_to_string(manifest).unwrap_or_default();
+
+    let mut names = HashSet::new();
+    let mut code = String::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // without an input file, the input is input/2020/day<N>.txt
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (day, part, file, expected) = match fields[..] {
+            [day, part, file, expected] => (day, part, Some(file), expected),
+            [day, part, expected] => (day, part, None, expected),
+            _ => panic!(
+                "{}:{}: expected `<day> <part> [input file] <answer>`",
+                manifest.display(),
+                i + 1
+            ),
+        };
+        let day: u32 = day.parse().expect("invalid day in fixture manifest");
+        let part: u32 = part.parse().expect("invalid part in fixture manifest");
+
+        // day07_2.txt part 2 becomes day07_2_p2, the real input for day 7 day7_p2
+        let default = format!("day{}", day);
+        let stem: String = file
+            .map_or(default.as_str(), |file| file.trim_end_matches(".txt"))
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let mut name = format!("{}_p{}", stem.to_lowercase(), part);
+        if name.starts_with(|c: char| c.is_ascii_digit()) {
+            name = format!("fixture_{}", name);
+        }
+        if !names.insert(name.clone()) {
+            name = format!("{}_line{}", name, i + 1);
+        }
+
+        let call = match file {
+            Some(file) => format!(
+                "check({}, {}, {:?}, {:?}, {})",
+                day,
+                part,
+                file,
+                expected,
+                i + 1
+            ),
+            None => format!("check_input({}, {}, {:?}, {})", day, part, expected, i + 1),
+        };
+        code += &format!("#[test]\nfn {}() {{\n    {};\n}}\n\n", name, call);
+    }
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("fixtures.rs");
+    fs::write(out, code).unwrap();
+}
@@ -0,0 +1,467 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+use crate::csv;
+use crate::d02::{
+    All, Any, CharClass, Classes, CountRange, Forbidden, MinEntropy, Not, Policy, PolicyPass,
+    PositionsAnd, PositionsXor, Report,
+};
+use crate::error::position;
+
+// Audits of day 2 password databases against policies loaded from a file.
+//
+// A policy file has one policy expression per line, a password has to meet all of them.
+// Blank lines and lines starting with # are skipped, but a file has to have at least one
+// policy. An expression combines the rules with `and`, `or`, `not` and parentheses, `and`
+// binding tighter than `or`:
+//
+//     count                         the line's letter occurs min to max times
+//     xor                           the letter is at exactly one of the positions min and max
+//     both                          the letter is at both positions
+//     forbid "<text>" ...           none of the quoted texts occur in the password
+//     entropy <bits>                see d02::entropy
+//     classes <n> of <class> ...    characters from n of lower, upper, digit and symbol
+//
+// This is the same form that Policy::describe gives, quoted texts included: they take the
+// escapes that Debug writes for a string, see unquote.
+
+// Where a policy file is wrong. The positions are in the policy file, not the password database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyError {
+    pub line: usize,
+    pub column: usize,
+    // None when the line ended too early
+    pub text: Option<String>,
+    pub message: String,
+}
+
+impl PolicyError {
+    // `token` must be a slice of `text`, an empty one at the end of a line
+    fn at(text: &str, token: &str, message: &str) -> Self {
+        let (line, column) = position(text, token);
+        PolicyError {
+            line,
+            column,
+            text: Some(token.to_string()).filter(|token| !token.is_empty()),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl Display for PolicyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}", self.message);
+        }
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )?;
+        match &self.text {
+            Some(text) => write!(f, " `{}`", text),
+            None => write!(f, " at end of line"),
+        }
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+pub fn parse_policies(text: &str) -> Result<All, PolicyError> {
+    let mut policies = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let mut parser = Parser {
+            text,
+            tokens: tokens(text, line)?,
+            end: &line[line.len()..],
+            next: 0,
+        };
+        let policy = parser.expression()?;
+        if let Some(token) = parser.peek() {
+            return Err(parser.error(token, "expected `and`, `or` or the end"));
+        }
+        policies.push(policy);
+    }
+    // an empty file would pass every password
+    if policies.is_empty() {
+        return Err(PolicyError {
+            line: 0,
+            column: 0,
+            text: None,
+            message: "no policies".to_string(),
+        });
+    }
+    Ok(All(policies))
+}
+
+// words, parentheses and quoted strings, as slices of the line
+fn tokens<'a>(text: &'a str, line: &'a str) -> Result<Vec<&'a str>, PolicyError> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let end = match c {
+            c if c.is_whitespace() => continue,
+            '(' | ')' => start + 1,
+            '"' => {
+                let mut escaped = false;
+                loop {
+                    match chars.next() {
+                        Some((i, '"')) if !escaped => break i + 1,
+                        Some((_, c)) => escaped = !escaped && c == '\\',
+                        None => {
+                            let message = "unterminated string";
+                            return Err(PolicyError::at(text, &line[start..], message));
+                        }
+                    }
+                }
+            }
+            _ => {
+                let mut end = line.len();
+                while let Some((i, c)) = chars.peek() {
+                    if c.is_whitespace() || *c == '(' || *c == ')' || *c == '"' {
+                        end = *i;
+                        break;
+                    }
+                    chars.next();
+                }
+                end
+            }
+        };
+        tokens.push(&line[start..end]);
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<&'a str>,
+    // an empty slice at the end of the line, where errors about missing tokens point
+    end: &'a str,
+    next: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.next).copied()
+    }
+
+    fn take(&mut self, message: &str) -> Result<&'a str, PolicyError> {
+        let token = self.peek().ok_or_else(|| self.error(self.end, message))?;
+        self.next += 1;
+        Ok(token)
+    }
+
+    fn error(&self, token: &str, message: &str) -> PolicyError {
+        PolicyError::at(self.text, token, message)
+    }
+
+    fn expression(&mut self) -> Result<Box<dyn Policy>, PolicyError> {
+        let mut any = vec![self.term()?];
+        while self.peek() == Some("or") {
+            self.next += 1;
+            any.push(self.term()?);
+        }
+        Ok(if any.len() == 1 {
+            any.remove(0)
+        } else {
+            Box::new(Any(any))
+        })
+    }
+
+    fn term(&mut self) -> Result<Box<dyn Policy>, PolicyError> {
+        let mut all = vec![self.factor()?];
+        while self.peek() == Some("and") {
+            self.next += 1;
+            all.push(self.factor()?);
+        }
+        Ok(if all.len() == 1 {
+            all.remove(0)
+        } else {
+            Box::new(All(all))
+        })
+    }
+
+    fn factor(&mut self) -> Result<Box<dyn Policy>, PolicyError> {
+        let token = self.take("expected a policy")?;
+        let policy: Box<dyn Policy> = match token {
+            "not" => Box::new(Not(self.factor()?)),
+            "(" => {
+                let policy = self.expression()?;
+                let close = self.take("expected `)`")?;
+                if close != ")" {
+                    return Err(self.error(close, "expected `)`"));
+                }
+                policy
+            }
+            "count" => Box::new(CountRange),
+            "xor" => Box::new(PositionsXor),
+            "both" => Box::new(PositionsAnd),
+            "forbid" => {
+                let mut forbidden = Vec::new();
+                while let Some(token) = self.peek().filter(|t| t.starts_with('"')) {
+                    self.next += 1;
+                    let text =
+                        unquote(token).map_err(|escape| self.error(escape, "unknown escape"))?;
+                    forbidden.push(text);
+                }
+                if forbidden.is_empty() {
+                    let at = self.peek().unwrap_or(self.end);
+                    return Err(self.error(at, "expected a quoted string"));
+                }
+                Box::new(Forbidden(forbidden))
+            }
+            "entropy" => {
+                let bits = self.take("expected a number of bits")?;
+                match bits.parse::<f64>() {
+                    Ok(bits) if bits.is_finite() => Box::new(MinEntropy(bits)),
+                    _ => return Err(self.error(bits, "expected a number of bits")),
+                }
+            }
+            "classes" => {
+                let n = self.take("expected a number of classes")?;
+                let at_least = n
+                    .parse()
+                    .map_err(|_| self.error(n, "expected a number of classes"))?;
+                let of = self.take("expected `of`")?;
+                if of != "of" {
+                    return Err(self.error(of, "expected `of`"));
+                }
+                let mut classes = Vec::new();
+                while let Some(class) = self.peek().and_then(class) {
+                    self.next += 1;
+                    classes.push(class);
+                }
+                if classes.is_empty() {
+                    let at = self.peek().unwrap_or(self.end);
+                    return Err(self.error(at, "expected lower, upper, digit or symbol"));
+                }
+                Box::new(Classes { classes, at_least })
+            }
+            _ => return Err(self.error(token, "unknown policy")),
+        };
+        Ok(policy)
+    }
+}
+
+fn class(name: &str) -> Option<CharClass> {
+    CharClass::ALL.iter().copied().find(|c| c.name() == name)
+}
+
+// The text between the quotes with the escapes replaced, the same ones Debug writes: \" \' \\
+// \n \r \t \0 and \u{<hex>}. On error, returns the escape that is wrong.
+fn unquote(token: &str) -> Result<String, &str> {
+    let inner = &token[1..token.len() - 1];
+    let mut out = String::new();
+    let mut chars = inner.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let escaped = match chars.next() {
+            Some((_, 'n')) => '\n',
+            Some((_, 'r')) => '\r',
+            Some((_, 't')) => '\t',
+            Some((_, '0')) => '\0',
+            Some((_, c @ '"')) | Some((_, c @ '\'')) | Some((_, c @ '\\')) => c,
+            Some((_, 'u')) if chars.peek().map(|(_, c)| *c) == Some('{') => {
+                let end = inner[start..]
+                    .find('}')
+                    .map(|i| start + i + 1)
+                    .ok_or(&inner[start..])?;
+                let escape = &inner[start..end];
+                while chars.peek().is_some_and(|(i, _)| *i < end) {
+                    chars.next();
+                }
+                u32::from_str_radix(&escape[3..escape.len() - 1], 16)
+                    .ok()
+                    .and_then(std::char::from_u32)
+                    .ok_or(escape)?
+            }
+            Some((i, c)) => return Err(&inner[start..i + c.len_utf8()]),
+            None => return Err(&inner[start..]),
+        };
+        out.push(escaped);
+    }
+    Ok(out)
+}
+
+pub struct Audit {
+    pub checked: usize,
+    // the failing lines, numbered from 1, with their reports
+    pub failing: Vec<(usize, Report)>,
+    // how many lines each policy failed on
+    pub reasons: BTreeMap<String, usize>,
+}
+
+pub fn audit(passes: &[PolicyPass], policy: &dyn Policy) -> Audit {
+    let mut failing = Vec::new();
+    let mut reasons = BTreeMap::new();
+    for (i, pass) in passes.iter().enumerate() {
+        let report = pass.report(policy);
+        if report.passed() {
+            continue;
+        }
+        for failure in &report.failures {
+            *reasons.entry(failure.policy.clone()).or_insert(0) += 1;
+        }
+        failing.push((i + 1, report));
+    }
+    Audit {
+        checked: passes.len(),
+        failing,
+        reasons,
+    }
+}
+
+impl Audit {
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "{} checked, {} passed, {} failed\n",
+            self.checked,
+            self.checked - self.failing.len(),
+            self.failing.len()
+        );
+        for (policy, count) in &self.reasons {
+            out.push_str(&format!("{:>8} {}\n", count, policy));
+        }
+        out
+    }
+
+    // a row for every failure of every failing line
+    pub fn to_csv(&self, passes: &[PolicyPass]) -> String {
+        let mut out = String::from("line,min,max,letter,password,policy,reason\n");
+        for (line, report) in &self.failing {
+            let pass = &passes[line - 1];
+            for failure in &report.failures {
+                out.push_str(&format!(
+                    "{},{},{},{},{},{},{}\n",
+                    line,
+                    pass.get_min(),
+                    pass.get_max(),
+                    csv::field(&pass.get_letter().to_string()),
+                    csv::field(pass.get_password()),
+                    csv::field(&failure.policy),
+                    csv::field(&failure.reason)
+                ));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::d02::parse_input;
+
+    #[test]
+    fn policy_files() {
+        let text = "# part 2 and some\n\ncount or xor\n  not forbid \"a\\\"b\" \"cd\"\n\
+                    (entropy 2.5 and both) or classes 2 of upper digit symbol";
+        let policy = parse_policies(text).unwrap();
+        assert_eq!(
+            "((count or xor) and not forbid \"a\\\"b\" \"cd\" and \
+             ((entropy 2.5 and both) or classes 2 of upper digit symbol))",
+            policy.describe()
+        );
+        // describe gives the same policy back
+        for line in &policy.0 {
+            let again = parse_policies(&line.describe()).unwrap();
+            assert_eq!(line.describe(), again.0[0].describe());
+        }
+        assert_eq!(
+            "(count or (xor and not count))",
+            parse_policies("count or xor and not count").unwrap().0[0].describe()
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        // what describe writes for any text comes back as the same text
+        let forbidden = Forbidden(vec![
+            "tab\there".to_string(),
+            "a\rb\0c\u{7}d'\\\"\u{200b}\u{301}é".to_string(),
+        ]);
+        let described = forbidden.describe();
+        assert!(described.contains("\\r") && described.contains("\\u{7}"));
+        let policy = parse_policies(&described).unwrap();
+        assert_eq!(described, policy.0[0].describe());
+
+        let policy = parse_policies("forbid \"\\'\\u{41}\"").unwrap();
+        assert_eq!("forbid \"'A\"", policy.0[0].describe());
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text| parse_policies(text).err().unwrap().to_string();
+        assert_eq!(
+            "line 2, column 5: unknown policy `cont`",
+            error("count\nnot cont")
+        );
+        assert_eq!(
+            "line 1, column 7: expected `and`, `or` or the end `xor`",
+            error("count xor")
+        );
+        assert_eq!(
+            "line 1, column 7: expected `)` at end of line",
+            error("(count")
+        );
+        assert_eq!(
+            "line 2, column 7: expected a quoted string at end of line",
+            error("count\nforbid")
+        );
+        assert_eq!(
+            "line 1, column 8: unterminated string `\"ab`",
+            error("forbid \"ab")
+        );
+        assert_eq!(
+            "line 1, column 14: expected lower, upper, digit or symbol `digits`",
+            error("classes 1 of digits")
+        );
+        assert_eq!(
+            "line 1, column 9: expected a number of bits `lots`",
+            error("entropy lots")
+        );
+        assert_eq!(
+            "line 1, column 11: unknown escape `\\x`",
+            error("forbid \"ab\\x\"")
+        );
+        assert_eq!(
+            "line 1, column 9: unknown escape `\\u{d800}`",
+            error("forbid \"\\u{d800}\"")
+        );
+        assert_eq!(
+            "line 1, column 9: unknown escape `\\u{41`",
+            error("forbid \"\\u{41\"")
+        );
+        assert_eq!("no policies", error(""));
+        assert_eq!("no policies", error("# nothing\n\n"));
+    }
+
+    #[test]
+    fn audits() {
+        let passes = parse_input("1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc\n1-2 a: a,\"b");
+        let policy = parse_policies("count\nforbid \"cc\"").unwrap();
+        let audit = audit(&passes, &policy);
+
+        assert_eq!(
+            vec![2, 3],
+            audit.failing.iter().map(|f| f.0).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "4 checked, 2 passed, 2 failed\n       1 count\n       1 forbid \"cc\"\n",
+            audit.summary()
+        );
+        assert_eq!(
+            "line,min,max,letter,password,policy,reason\n\
+             2,1,3,b,cdefg,count,\"b occurs 0 times, not 1 to 3\"\n\
+             3,2,9,c,ccccccccc,\"forbid \"\"cc\"\"\",\"contains \"\"cc\"\"\"\n",
+            audit.to_csv(&passes)
+        );
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+use std::str::FromStr;
+
+use crate::grid::{Grid, ADJACENT};
+
+// A cellular automaton over any topology, with a Life-like rule.
+//
+// Only the live cells are stored. Each step counts the live neighbours of every cell next to
+// a live cell, so unbounded topologies work as long as the rule doesn't give birth to cells
+// with no live neighbours (B0). Bounded topologies list all of their cells and allow B0.
+
+pub trait Topology {
+    type Cell: Clone + Eq + Hash;
+
+    fn neighbours(&self, cell: &Self::Cell) -> Vec<Self::Cell>;
+
+    // every cell, or None for an unbounded topology
+    fn cells(&self) -> Option<Vec<Self::Cell>> {
+        None
+    }
+}
+
+// the 3^N - 1 cells around a cell in N dimensions, Square is the usual 2d plane
+pub struct Moore<const N: usize>;
+
+pub type Square = Moore<2>;
+
+impl<const N: usize> Topology for Moore<N> {
+    type Cell = [i32; N];
+
+    fn neighbours(&self, cell: &[i32; N]) -> Vec<[i32; N]> {
+        let count = 3_usize.pow(N as u32);
+        let mut neighbours = Vec::with_capacity(count - 1);
+        for i in 0..count {
+            let mut neighbour = *cell;
+            let mut rest = i;
+            for c in neighbour.iter_mut() {
+                *c += (rest % 3) as i32 - 1;
+                rest /= 3;
+            }
+            if neighbour != *cell {
+                neighbours.push(neighbour);
+            }
+        }
+        neighbours
+    }
+}
+
+// hexagons in axial coordinates (q, r), in the order e, se, sw, w, nw, ne for pointy tops
+pub const HEX_DIRECTIONS: [(i32, i32); 6] = [(1, 0), (0, 1), (-1, 1), (-1, 0), (0, -1), (1, -1)];
+
+pub struct Hex;
+
+impl Topology for Hex {
+    type Cell = (i32, i32);
+
+    fn neighbours(&self, (q, r): &(i32, i32)) -> Vec<(i32, i32)> {
+        HEX_DIRECTIONS
+            .iter()
+            .map(|(dq, dr)| (q + dq, r + dr))
+            .collect()
+    }
+}
+
+// The cells of a grid that take part, such as the seats of day 11. Neighbours are either the
+// adjacent cells or the first cell seen in each of the 8 directions, skipping the others.
+pub struct Bounded {
+    neighbours: Grid<Vec<(usize, usize)>>,
+    cells: Vec<(usize, usize)>,
+}
+
+impl Bounded {
+    pub fn adjacent(mask: &Grid<bool>) -> Self {
+        Self::new(mask, |x, y| {
+            mask.neighbours8(x, y).filter(|pos| mask[*pos]).collect()
+        })
+    }
+
+    pub fn line_of_sight(mask: &Grid<bool>) -> Self {
+        Self::new(mask, |x, y| {
+            ADJACENT
+                .iter()
+                .filter_map(|(dx, dy)| mask.ray(x, y, *dx, *dy).find(|pos| mask[*pos]))
+                .collect()
+        })
+    }
+
+    fn new<F>(mask: &Grid<bool>, mut f: F) -> Self
+    where
+        F: FnMut(usize, usize) -> Vec<(usize, usize)>,
+    {
+        let mut neighbours = Grid::new(mask.width(), mask.height(), Vec::new());
+        let mut cells = Vec::new();
+        for ((x, y), active) in mask.iter() {
+            if *active {
+                neighbours.set(x, y, f(x, y));
+                cells.push((x, y));
+            }
+        }
+        Bounded { neighbours, cells }
+    }
+}
+
+impl Topology for Bounded {
+    type Cell = (usize, usize);
+
+    fn neighbours(&self, cell: &(usize, usize)) -> Vec<(usize, usize)> {
+        self.neighbours[*cell].clone()
+    }
+
+    fn cells(&self) -> Option<Vec<(usize, usize)>> {
+        Some(self.cells.clone())
+    }
+}
+
+// Life-like rule in B/S notation, B3/S23 is Conway's game of life: a dead cell with 3 live
+// neighbours is born, a live cell with 2 or 3 survives. Counts are single digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    birth: u16,
+    survival: u16,
+}
+
+impl Rule {
+    pub fn born(&self, neighbours: usize) -> bool {
+        neighbours < 10 && self.birth & (1 << neighbours) != 0
+    }
+
+    pub fn survives(&self, neighbours: usize) -> bool {
+        neighbours < 10 && self.survival & (1 << neighbours) != 0
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let counts = |digits: &str| {
+            digits.chars().try_fold(0_u16, |mask, c| {
+                c.to_digit(10)
+                    .map(|n| mask | (1 << n))
+                    .ok_or_else(|| format!("invalid rule {}: {} is not a count", s, c))
+            })
+        };
+
+        let mut parts = s.split('/');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(birth), Some(survival), None)
+                if birth.starts_with('B') && survival.starts_with('S') =>
+            {
+                Ok(Rule {
+                    birth: counts(&birth[1..])?,
+                    survival: counts(&survival[1..])?,
+                })
+            }
+            _ => Err(format!("invalid rule {}: expected B<counts>/S<counts>", s)),
+        }
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let counts = |mask: u16| -> String {
+            (0..10)
+                .filter(|n| mask & (1 << n) != 0)
+                .map(|n| n.to_string())
+                .collect()
+        };
+        write!(f, "B{}/S{}", counts(self.birth), counts(self.survival))
+    }
+}
+
+pub struct Automaton<T: Topology> {
+    topology: T,
+    rule: Rule,
+    live: HashSet<T::Cell>,
+    // every cell when bounded, to find births with no live neighbours
+    cells: Option<Vec<T::Cell>>,
+    // population of every generation so far, starting with the initial one
+    history: Vec<usize>,
+}
+
+impl<T: Topology> Automaton<T> {
+    pub fn new<I>(topology: T, rule: Rule, live: I) -> Self
+    where
+        I: IntoIterator<Item = T::Cell>,
+    {
+        let cells = topology.cells();
+        assert!(
+            !rule.born(0) || cells.is_some(),
+            "{} needs a bounded topology",
+            rule
+        );
+
+        let live: HashSet<T::Cell> = live.into_iter().collect();
+        let history = vec![live.len()];
+        Automaton {
+            topology,
+            rule,
+            live,
+            cells,
+            history,
+        }
+    }
+
+    pub fn population(&self) -> usize {
+        self.live.len()
+    }
+
+    pub fn history(&self) -> &[usize] {
+        &self.history
+    }
+
+    pub fn is_alive(&self, cell: &T::Cell) -> bool {
+        self.live.contains(cell)
+    }
+
+    pub fn live(&self) -> impl Iterator<Item = &T::Cell> {
+        self.live.iter()
+    }
+
+    // returns whether any cell changed
+    pub fn step(&mut self) -> bool {
+        let mut counts: HashMap<T::Cell, usize> = HashMap::new();
+        for cell in &self.live {
+            for neighbour in self.topology.neighbours(cell) {
+                *counts.entry(neighbour).or_insert(0) += 1;
+            }
+        }
+
+        let mut next = HashSet::new();
+        for cell in &self.live {
+            if self.rule.survives(counts.get(cell).copied().unwrap_or(0)) {
+                next.insert(cell.clone());
+            }
+        }
+        for (cell, count) in &counts {
+            if !self.live.contains(cell) && self.rule.born(*count) {
+                next.insert(cell.clone());
+            }
+        }
+        if let (true, Some(cells)) = (self.rule.born(0), &self.cells) {
+            for cell in cells {
+                if !self.live.contains(cell) && !counts.contains_key(cell) {
+                    next.insert(cell.clone());
+                }
+            }
+        }
+
+        let changed = next != self.live;
+        self.live = next;
+        self.history.push(self.live.len());
+        changed
+    }
+
+    // the population after the given number of steps
+    pub fn run(&mut self, steps: usize) -> usize {
+        self.run_with(steps, |_| {})
+    }
+
+    // the population once a step changes nothing, which may never happen
+    pub fn run_until_stable(&mut self) -> usize {
+        self.run_until_stable_with(|_| {})
+    }
+
+    // like run, calling f with the starting generation and after every step
+    pub fn run_with<F: FnMut(&Self)>(&mut self, steps: usize, mut f: F) -> usize {
+        f(self);
+        for _ in 0..steps {
+            self.step();
+            f(self);
+        }
+        self.population()
+    }
+
+    pub fn run_until_stable_with<F: FnMut(&Self)>(&mut self, mut f: F) -> usize {
+        f(self);
+        while self.step() {
+            f(self);
+        }
+        self.population()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules() {
+        let rule: Rule = "B3/S23".parse().unwrap();
+        assert!(rule.born(3) && !rule.born(2));
+        assert!(rule.survives(2) && rule.survives(3) && !rule.survives(4));
+        assert!(!rule.survives(26));
+        assert_eq!("B3/S23", rule.to_string());
+        assert_eq!("B0/S", "B0/S".parse::<Rule>().unwrap().to_string());
+
+        assert!("B3S23".parse::<Rule>().is_err());
+        assert!("B3/Sx".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn blinker() {
+        let life = "B3/S23".parse().unwrap();
+        let mut blinker = Automaton::new(Square {}, life, vec![[0, -1], [0, 0], [0, 1]]);
+        assert!(blinker.step());
+        assert!(blinker.is_alive(&[1, 0]) && !blinker.is_alive(&[0, 1]));
+        assert_eq!(3, blinker.run(3));
+        assert_eq!(&[3, 3, 3, 3, 3], blinker.history());
+
+        let mut generations = 0;
+        blinker.run_with(2, |b| generations += b.population());
+        assert_eq!(9, generations);
+        assert_eq!(26, Moore::<3>.neighbours(&[0, 0, 0]).len());
+    }
+
+    #[test]
+    fn bounded() {
+        let mask = Grid::parse(0, "#.#\n...\n#.#", |c| Some(c == '#')).unwrap();
+        let rule = "B0/S0".parse().unwrap();
+
+        let mut seats = Automaton::new(Bounded::adjacent(&mask), rule, vec![]);
+        assert_eq!(4, seats.run_until_stable());
+        assert_eq!(&[0, 4, 4], seats.history());
+
+        let mut seats = Automaton::new(Bounded::line_of_sight(&mask), rule, vec![]);
+        assert_eq!(0, seats.run(2));
+    }
+}
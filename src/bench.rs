@@ -0,0 +1,195 @@
+use std::time::{Duration, Instant};
+
+use crate::csv;
+use crate::solution::{self, Error};
+
+// Timings of a day's generator and solver on one input over several runs, and reports of
+// them as JSON or CSV so that runs can be compared over time. Times are in nanoseconds.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    pub fn new(mut times: Vec<Duration>) -> Self {
+        assert!(!times.is_empty(), "no times to summarise");
+        times.sort_unstable();
+        let mid = times.len() / 2;
+        let median = if times.len().is_multiple_of(2) {
+            (times[mid - 1] + times[mid]) / 2
+        } else {
+            times[mid]
+        };
+        Stats {
+            min: times[0],
+            median,
+            max: times[times.len() - 1],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Measurement {
+    pub day: u32,
+    pub part: u32,
+    // where the input came from, a path or a description of a generated input
+    pub input: String,
+    pub bytes: usize,
+    pub runs: usize,
+    pub answer: String,
+    pub generator: Stats,
+    pub solver: Stats,
+}
+
+// Runs the part up to `runs` times, fewer if they take longer than `budget` in total. The
+// first run always counts, slow parts get at least one timing.
+pub fn measure(
+    day: u32,
+    part: u32,
+    name: &str,
+    input: &str,
+    runs: usize,
+    budget: Duration,
+) -> Result<Measurement, Error> {
+    let start = Instant::now();
+    let mut generator = Vec::with_capacity(runs);
+    let mut solver = Vec::with_capacity(runs);
+    let mut answer = String::new();
+    while generator.len() < runs.max(1) && (generator.is_empty() || start.elapsed() < budget) {
+        let timed = solution::solve_timed(day, part, input)?;
+        generator.push(timed.parse);
+        solver.push(timed.solve);
+        answer = timed.answer.to_string();
+    }
+
+    Ok(Measurement {
+        day,
+        part,
+        input: name.to_string(),
+        bytes: input.len(),
+        runs: generator.len(),
+        answer,
+        generator: Stats::new(generator),
+        solver: Stats::new(solver),
+    })
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_stats(stats: &Stats) -> String {
+    format!(
+        "{{\"min_ns\": {}, \"median_ns\": {}, \"max_ns\": {}}}",
+        stats.min.as_nanos(),
+        stats.median.as_nanos(),
+        stats.max.as_nanos()
+    )
+}
+
+pub fn to_json(measurements: &[Measurement]) -> String {
+    let rows: Vec<String> = measurements
+        .iter()
+        .map(|m| {
+            format!(
+                "  {{\"day\": {}, \"part\": {}, \"input\": {}, \"bytes\": {}, \"runs\": {}, \
+                 \"answer\": {}, \"generator\": {}, \"solver\": {}}}",
+                m.day,
+                m.part,
+                json_string(&m.input),
+                m.bytes,
+                m.runs,
+                json_string(&m.answer),
+                json_stats(&m.generator),
+                json_stats(&m.solver)
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", rows.join(",\n"))
+}
+
+pub fn to_csv(measurements: &[Measurement]) -> String {
+    let mut out = String::from(
+        "day,part,input,bytes,runs,answer,generator_min_ns,generator_median_ns,\
+         generator_max_ns,solver_min_ns,solver_median_ns,solver_max_ns\n",
+    );
+    for m in measurements {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{}\n",
+            m.day,
+            m.part,
+            csv::field(&m.input),
+            m.bytes,
+            m.runs,
+            csv::field(&m.answer),
+            m.generator.min.as_nanos(),
+            m.generator.median.as_nanos(),
+            m.generator.max.as_nanos(),
+            m.solver.min.as_nanos(),
+            m.solver.median.as_nanos(),
+            m.solver.max.as_nanos()
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(times: &[u64]) -> Vec<Duration> {
+        times.iter().map(|t| Duration::from_millis(*t)).collect()
+    }
+
+    #[test]
+    fn stats() {
+        let stats = Stats::new(ms(&[5, 1, 3]));
+        assert_eq!(ms(&[1, 3, 5]), vec![stats.min, stats.median, stats.max]);
+        assert_eq!(
+            Duration::from_millis(4),
+            Stats::new(ms(&[8, 1, 5, 3])).median
+        );
+    }
+
+    #[test]
+    fn reports() {
+        let mut measurement = measure(1, 1, "day1", "1721\n299", 3, Duration::MAX).unwrap();
+        assert_eq!(
+            (3, 8, "514579"),
+            (measurement.runs, measurement.bytes, &*measurement.answer)
+        );
+        assert!(measure(1, 3, "day1", "1721\n299", 3, Duration::MAX).is_err());
+
+        let stats = Stats::new(ms(&[1, 2]));
+        measurement.input = "a \"b\", c".to_string();
+        measurement.generator = stats;
+        measurement.solver = stats;
+
+        let csv = to_csv(&[measurement.clone()]);
+        assert_eq!(
+            "1,1,\"a \"\"b\"\", c\",8,3,514579,1000000,1500000,2000000,1000000,1500000,2000000\n",
+            csv.lines()
+                .nth(1)
+                .map(|line| format!("{}\n", line))
+                .unwrap()
+        );
+        let json = to_json(&[measurement]);
+        assert!(json.contains("\"input\": \"a \\\"b\\\", c\""));
+        assert!(json.contains(
+            "\"solver\": {\"min_ns\": 1000000, \"median_ns\": 1500000, \"max_ns\": 2000000}"
+        ));
+    }
+}
@@ -0,0 +1,386 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use aoc_2020::audit;
+use aoc_2020::bench::{self, Measurement};
+use aoc_2020::d02;
+use aoc_2020::fixture;
+use aoc_2020::generate;
+use aoc_2020::render;
+use aoc_2020::runner::{self, Task};
+use aoc_2020::solution::{self, Timed, DAYS};
+
+const USAGE: &str = "usage:
+    aoc2020 run <day|all> [part] [--input <file|->] [--dir <dir>]
+    aoc2020 bench <day|all> [part] [--runs <n>] [--input <file|->] [--dir <dir>] [--json <file>] [--csv <file>]
+    aoc2020 parallel <day|all> [part] [--threads <n>] [--timeout <secs>] [--input <file|->] [--dir <dir>]
+    aoc2020 verify [manifest] [--dir <dir>]
+    aoc2020 audit <policy file> [--input <file|->] [--dir <dir>] [--csv <file>]
+    aoc2020 generate <day> [--seed <n>] [--size <n>]
+    aoc2020 render <11|12|17|24> <part> [--input <file|->] [--dir <dir>] [--out <dir>] [--format <txt|ppm|png>]
+
+Inputs are read from <dir>/day<N>.txt (default input/2020) unless --input is given,
+`-` reads from stdin. The manifest (default tests/fixtures/manifest.txt) has one
+`<day> <part> [input file] <answer>` per line, see src/fixture.rs. bench can also write its
+timings as JSON or CSV reports. parallel runs the parts concurrently, each for at most --timeout
+seconds when given, and prints a summary. audit checks the day 2 passwords against a policy file,
+see src/audit.rs, and can write the failing lines as CSV. generate prints a random
+input for the day and, on stderr, the answers it is known to have. render writes every step of
+a simulation to <out>/day<N>_part<P>_<step>.<format> (default frames, png).";
+
+struct Options {
+    args: Vec<String>,
+    input: Option<String>,
+    dir: String,
+    runs: usize,
+    seed: u64,
+    size: usize,
+    out: String,
+    format: render::Format,
+    json: Option<String>,
+    csv: Option<String>,
+    runner: runner::Config,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Options {
+            args: Vec::new(),
+            input: None,
+            dir: "input/2020".to_string(),
+            runs: 10,
+            seed: 0,
+            size: 100,
+            out: "frames".to_string(),
+            format: render::Format::Png,
+            json: None,
+            csv: None,
+            runner: runner::Config::default(),
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| format!("missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--input" | "-i" => options.input = Some(value()?),
+                "--dir" | "-d" => options.dir = value()?,
+                "--runs" | "-n" => {
+                    options.runs = value()?
+                        .parse()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .ok_or("--runs needs a positive number")?
+                }
+                "--seed" | "-s" => {
+                    options.seed = value()?.parse().map_err(|_| "--seed needs a number")?
+                }
+                "--size" => options.size = value()?.parse().map_err(|_| "--size needs a number")?,
+                "--out" | "-o" => options.out = value()?,
+                "--format" | "-f" => options.format = value()?.parse()?,
+                "--json" => options.json = Some(value()?),
+                "--csv" => options.csv = Some(value()?),
+                "--threads" | "-t" => {
+                    options.runner.threads = value()?
+                        .parse()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .ok_or("--threads needs a positive number")?
+                }
+                "--timeout" => {
+                    let secs: f64 = value()?
+                        .parse()
+                        .ok()
+                        .filter(|secs: &f64| *secs > 0.0 && secs.is_finite())
+                        .ok_or("--timeout needs a positive number of seconds")?;
+                    options.runner.timeout = Some(Duration::from_secs_f64(secs));
+                }
+                _ => options.args.push(arg.clone()),
+            }
+        }
+
+        Ok(options)
+    }
+
+    // the (day, part) pairs selected by `<day|all> [part]`
+    fn selection(&self) -> Result<Vec<(u32, u32)>, String> {
+        let days = match self.args.first().map(|s| s.as_str()) {
+            Some("all") => DAYS.to_vec(),
+            Some(day) => vec![parse_number(day, "day")?],
+            None => return Err(USAGE.to_string()),
+        };
+        if days.len() > 1 && self.input.is_some() {
+            return Err("--input can only be used with a single day".to_string());
+        }
+
+        let part = match self.args.get(1) {
+            Some(part) => Some(parse_number(part, "part")?),
+            None => None,
+        };
+
+        let mut selection = Vec::new();
+        for day in days {
+            if solution::parts(day) == 0 {
+                return Err(format!("day {} is not solved", day));
+            }
+            match part {
+                Some(part) => selection.push((day, part)),
+                None => selection.extend((1..=solution::parts(day)).map(|part| (day, part))),
+            }
+        }
+        Ok(selection)
+    }
+
+    fn read_input(&self, day: u32) -> Result<String, String> {
+        match &self.input {
+            Some(path) if path == "-" => {
+                let mut input = String::new();
+                io::stdin()
+                    .read_to_string(&mut input)
+                    .map_err(|e| format!("can't read stdin: {}", e))?;
+                // trimmed the same way as fixture::read_input
+                Ok(input.trim_end_matches(&['\r', '\n'][..]).to_string())
+            }
+            Some(path) => fixture::read_input(Path::new(path)),
+            None => {
+                let path = Path::new(&self.dir).join(format!("day{}.txt", day));
+                fixture::read_input(&path)
+            }
+        }
+    }
+}
+
+fn parse_number(s: &str, what: &str) -> Result<u32, String> {
+    s.parse().map_err(|_| format!("invalid {}: {}", what, s))
+}
+
+// inputs are read once per day, stdin can't be read twice
+fn with_inputs<F>(options: &Options, selection: &[(u32, u32)], mut f: F) -> Result<(), String>
+where
+    F: FnMut(u32, u32, &str) -> Result<(), String>,
+{
+    let mut current: Option<(u32, String)> = None;
+    for (day, part) in selection {
+        if current.as_ref().map(|(d, _)| d) != Some(day) {
+            current = Some((*day, options.read_input(*day)?));
+        }
+        let (_, input) = current.as_ref().unwrap();
+        f(*day, *part, input)?;
+    }
+    Ok(())
+}
+
+fn solve(day: u32, part: u32, input: &str) -> Result<Timed, String> {
+    solution::solve_timed(day, part, input).map_err(|e| e.to_string())
+}
+
+fn run(options: &Options) -> Result<bool, String> {
+    let selection = options.selection()?;
+    with_inputs(options, &selection, |day, part, input| {
+        let timed = solve(day, part, input)?;
+        println!(
+            "day {:2} part {}: {:<20} (generator {:?}, solver {:?})",
+            day, part, timed.answer, timed.parse, timed.solve
+        );
+        Ok(())
+    })?;
+    Ok(true)
+}
+
+fn bench(options: &Options) -> Result<bool, String> {
+    let selection = options.selection()?;
+    println!(
+        "{:>3} {:>4} {:>14} {:>14} {:>14} {:>14}",
+        "day", "part", "generator med", "solver min", "solver med", "solver max"
+    );
+
+    let mut total = Duration::default();
+    let mut measurements: Vec<Measurement> = Vec::new();
+    with_inputs(options, &selection, |day, part, input| {
+        let name = match &options.input {
+            Some(path) => path.clone(),
+            None => Path::new(&options.dir)
+                .join(format!("day{}.txt", day))
+                .display()
+                .to_string(),
+        };
+        let m = bench::measure(day, part, &name, input, options.runs, Duration::MAX)
+            .map_err(|e| e.to_string())?;
+        total += m.generator.median + m.solver.median;
+        println!(
+            "{:>3} {:>4} {:>14} {:>14} {:>14} {:>14}",
+            day,
+            part,
+            format!("{:?}", m.generator.median),
+            format!("{:?}", m.solver.min),
+            format!("{:?}", m.solver.median),
+            format!("{:?}", m.solver.max),
+        );
+        measurements.push(m);
+        Ok(())
+    })?;
+    println!("total (median): {:?}", total);
+
+    if let Some(path) = &options.json {
+        write_report(path, &bench::to_json(&measurements))?;
+    }
+    if let Some(path) = &options.csv {
+        write_report(path, &bench::to_csv(&measurements))?;
+    }
+    Ok(true)
+}
+
+fn write_report(path: &str, report: &str) -> Result<(), String> {
+    fs::write(path, report).map_err(|e| format!("can't write {}: {}", path, e))
+}
+
+fn parallel(options: &Options) -> Result<bool, String> {
+    let selection = options.selection()?;
+    let mut tasks = Vec::with_capacity(selection.len());
+    let mut current: Option<(u32, Option<Arc<str>>)> = None;
+    for (day, part) in selection {
+        if current.as_ref().map(|(d, _)| *d) != Some(day) {
+            // a day without an input is reported and left out, the others still run
+            let input = match options.read_input(day) {
+                Ok(input) => Some(Arc::from(input)),
+                Err(message) => {
+                    eprintln!("day {}: {}", day, message);
+                    None
+                }
+            };
+            current = Some((day, input));
+        }
+        if let Some((_, Some(input))) = &current {
+            tasks.push(Task {
+                day,
+                part,
+                input: Arc::clone(input),
+            });
+        }
+    }
+
+    let start = Instant::now();
+    let outcomes = runner::run(tasks, options.runner);
+    print!("{}", runner::summary(&outcomes, start.elapsed()));
+    Ok(outcomes
+        .iter()
+        .all(|outcome| matches!(outcome.status, runner::Status::Solved(_))))
+}
+
+fn verify(options: &Options) -> Result<bool, String> {
+    let manifest = options
+        .args
+        .first()
+        .map(|s| s.as_str())
+        .unwrap_or("tests/fixtures/manifest.txt");
+    let fixtures = fixture::load(Path::new(manifest), Path::new(&options.dir))?;
+
+    let mut failures = 0;
+    for fixture in &fixtures {
+        let label = format!(
+            "day {:2} part {} {}",
+            fixture.day,
+            fixture.part,
+            fixture.input.display()
+        );
+        match fixture.run().map(|answer| answer.to_string()) {
+            Ok(actual) if actual == fixture.expected => println!("{}: ok", label),
+            Ok(actual) => {
+                failures += 1;
+                println!(
+                    "{}: MISMATCH expected {} got {}",
+                    label, fixture.expected, actual
+                );
+            }
+            Err(message) => {
+                failures += 1;
+                println!("{}: ERROR {}", label, message);
+            }
+        }
+    }
+
+    println!("{} checked, {} failed", fixtures.len(), failures);
+    Ok(failures == 0)
+}
+
+fn audit(options: &Options) -> Result<bool, String> {
+    let path = match options.args.as_slice() {
+        [path] => path,
+        _ => return Err(USAGE.to_string()),
+    };
+    let text = fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path, e))?;
+    let policy = audit::parse_policies(&text).map_err(|e| format!("{}: {}", path, e))?;
+    let passes = d02::try_parse_input(&options.read_input(2)?).map_err(|e| e.to_string())?;
+
+    let audit = audit::audit(&passes, &policy);
+    print!("{}", audit.summary());
+    if let Some(path) = &options.csv {
+        write_report(path, &audit.to_csv(&passes))?;
+    }
+    Ok(true)
+}
+
+fn generate(options: &Options) -> Result<bool, String> {
+    let day = match options.args.first() {
+        Some(day) => parse_number(day, "day")?,
+        None => return Err(USAGE.to_string()),
+    };
+    let generated =
+        generate::generate(day, options.seed, options.size).map_err(|e| e.to_string())?;
+
+    println!("{}", generated.input);
+    for part in 1..=solution::parts(day) {
+        match generated.answer(part) {
+            Some(answer) => eprintln!("part {}: {}", part, answer),
+            None => eprintln!("part {}: unknown", part),
+        }
+    }
+    Ok(true)
+}
+
+fn render(options: &Options) -> Result<bool, String> {
+    let (day, part) = match options.args.as_slice() {
+        [day, part] => (parse_number(day, "day")?, parse_number(part, "part")?),
+        _ => return Err(USAGE.to_string()),
+    };
+    let frames = render::frames(day, part, &options.read_input(day)?)?;
+
+    let prefix = format!("day{}_part{}", day, part);
+    render::write_frames(Path::new(&options.out), &prefix, &frames, options.format)
+        .map_err(|e| format!("can't write frames to {}: {}", options.out, e))?;
+    println!("{} frames written to {}", frames.len(), options.out);
+    Ok(true)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = args.first().map(|s| s.as_str()).unwrap_or("");
+
+    let result = Options::parse(args.get(1..).unwrap_or(&[])).and_then(|options| match command {
+        "run" => run(&options),
+        "bench" => bench(&options),
+        "parallel" => parallel(&options),
+        "verify" => verify(&options),
+        "audit" => audit(&options),
+        "generate" => generate(&options),
+        "render" => render(&options),
+        _ => Err(USAGE.to_string()),
+    });
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+// Fields for the CSV reports of bench and audit.
+
+// quoted only when it has to be
+pub fn field(s: &str) -> String {
+    if s.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting() {
+        assert_eq!("plain text", field("plain text"));
+        assert_eq!("\"a,b\"", field("a,b"));
+        assert_eq!("\"say \"\"hi\"\"\"", field("say \"hi\""));
+        assert_eq!("\"two\nlines\"", field("two\nlines"));
+    }
+}
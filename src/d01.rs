@@ -1,41 +1,161 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::error::{parse_numbers, ParseError};
+
 #[aoc_generator(day1)]
 pub fn parse_input(input: &str) -> Vec<i32> {
-    input.lines().map(|l| l.parse::<i32>().unwrap()).collect()
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<Vec<i32>, ParseError> {
+    parse_numbers(1, input)
 }
 
 #[aoc(day1, part1)]
 pub fn part1(entries: &[i32]) -> i32 {
-    for i in 0..entries.len() {
-        for j in 0..entries.len() {
-            if i != j {
-                let a = entries.get(i).unwrap();
-                let b = entries.get(j).unwrap();
-                if a + b == 2020 {
-                    return a * b;
+    product(entries, 2)
+}
+
+#[aoc(day1, part2)]
+pub fn part2(entries: &[i32]) -> i32 {
+    product(entries, 3)
+}
+
+fn product(entries: &[i32], k: usize) -> i32 {
+    let indices = k_sum(entries, k, 2020).expect("not found");
+    indices.iter().map(|i| entries[*i]).product()
+}
+
+// Indices of k different entries that add up to target, in increasing order. Pairs are found
+// by hashing, larger groups by sorting and fixing all but two entries, which are then found
+// with two pointers.
+pub fn k_sum(entries: &[i32], k: usize, target: i64) -> Option<Vec<usize>> {
+    let mut first = None;
+    combinations(entries, k, target, |indices| {
+        first = Some(indices);
+        true
+    });
+    first
+}
+
+// One combination of indices for every distinct multiset of values that adds up to target.
+pub fn k_sums(entries: &[i32], k: usize, target: i64) -> Vec<Vec<usize>> {
+    let mut all = Vec::new();
+    combinations(entries, k, target, |indices| {
+        all.push(indices);
+        false
+    });
+    all
+}
+
+// calls found with each combination until it returns true
+fn combinations<F>(entries: &[i32], k: usize, target: i64, mut found: F)
+where
+    F: FnMut(Vec<usize>) -> bool,
+{
+    let mut report = |indices: &[usize]| {
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        found(indices)
+    };
+
+    match k {
+        0 => {
+            if target == 0 {
+                report(&[]);
+            }
+        }
+        1 => {
+            if let Some(i) = entries.iter().position(|e| i64::from(*e) == target) {
+                report(&[i]);
+            }
+        }
+        2 => {
+            // the first index of every value seen so far
+            let mut seen: HashMap<i64, usize> = HashMap::new();
+            let mut pairs: HashSet<(i64, i64)> = HashSet::new();
+            for (j, entry) in entries.iter().enumerate() {
+                let b = i64::from(*entry);
+                let a = target - b;
+                if let Some(i) = seen.get(&a) {
+                    if pairs.insert((a.min(b), a.max(b))) && report(&[*i, j]) {
+                        return;
+                    }
                 }
+                seen.entry(b).or_insert(j);
             }
         }
+        _ => {
+            let mut sorted: Vec<(i64, usize)> = entries
+                .iter()
+                .enumerate()
+                .map(|(i, e)| (i64::from(*e), i))
+                .collect();
+            sorted.sort_unstable();
+            search(&sorted, k, target, &mut Vec::with_capacity(k), &mut report);
+        }
     }
-    panic!("not found");
 }
 
-#[aoc(day1, part2)]
-pub fn part2(entries: &[i32]) -> i32 {
-    for i in 0..entries.len() {
-        for j in 0..entries.len() {
-            for k in 0..entries.len() {
-                if i != j {
-                    let a = entries.get(i).unwrap();
-                    let b = entries.get(j).unwrap();
-                    let c = entries.get(k).unwrap();
-                    if a + b + c == 2020 {
-                        return a * b * c;
-                    }
+// true once found has asked to stop
+fn search<F>(
+    sorted: &[(i64, usize)],
+    k: usize,
+    target: i64,
+    chosen: &mut Vec<usize>,
+    found: &mut F,
+) -> bool
+where
+    F: FnMut(&[usize]) -> bool,
+{
+    if k == 2 {
+        if sorted.len() < 2 {
+            return false;
+        }
+        let (mut lo, mut hi) = (0, sorted.len() - 1);
+        while lo < hi {
+            let (a, b) = (sorted[lo].0, sorted[hi].0);
+            if a + b < target {
+                lo += 1;
+            } else if a + b > target {
+                hi -= 1;
+            } else {
+                chosen.extend(&[sorted[lo].1, sorted[hi].1]);
+                let stop = found(chosen);
+                chosen.truncate(chosen.len() - 2);
+                if stop {
+                    return true;
+                }
+                while lo < hi && sorted[lo].0 == a {
+                    lo += 1;
+                }
+                while lo < hi && sorted[hi].0 == b {
+                    hi -= 1;
                 }
             }
         }
+        return false;
+    }
+
+    let sum = |values: &[(i64, usize)]| values.iter().map(|(v, _)| v).sum::<i64>();
+    for i in 0..sorted.len() {
+        if sorted.len() - i < k || sum(&sorted[i..i + k]) > target {
+            break;
+        }
+        if i > 0 && sorted[i].0 == sorted[i - 1].0 {
+            continue;
+        }
+        if sorted[i].0 + sum(&sorted[sorted.len() - (k - 1)..]) < target {
+            continue;
+        }
+        chosen.push(sorted[i].1);
+        let stop = search(&sorted[i + 1..], k - 1, target - sorted[i].0, chosen, found);
+        chosen.pop();
+        if stop {
+            return true;
+        }
     }
-    panic!("not found")
+    false
 }
 
 #[cfg(test)]
@@ -47,5 +167,52 @@ mod tests {
     fn p1_t1() {
         let entries = vec![1721, 979, 366, 299, 675, 1456];
         assert_eq!(514579, part1(&entries));
+        assert_eq!(241861950, part2(&entries));
+    }
+
+    #[test]
+    fn entries_used_once() {
+        // 1010 + 1010 would need the same entry twice
+        let entries = vec![1010, 1000, 10, 1721, 299, 7, 1003];
+        assert_eq!(Some(vec![3, 4]), k_sum(&entries, 2, 2020));
+        assert_eq!(Some(vec![1, 5, 6]), k_sum(&entries, 3, 2010));
+        assert_eq!(None, k_sum(&[1010], 2, 2020));
+        assert_eq!(None, k_sum(&[1010, 5], 3, 2025));
+    }
+
+    #[test]
+    fn any_target_and_arity() {
+        let entries = vec![4, -3, 0, 2, -1, 5, 1];
+        assert_eq!(Some(vec![]), k_sum(&entries, 0, 0));
+        assert_eq!(Some(vec![3]), k_sum(&entries, 1, 2));
+        assert_eq!(Some(vec![1, 4]), k_sum(&entries, 2, -4));
+        assert_eq!(None, k_sum(&entries, 2, 10));
+        let four = k_sum(&entries, 4, 3).unwrap();
+        assert_eq!(3, four.iter().map(|i| entries[*i]).sum::<i32>());
+        assert_eq!(Some((0..7).collect()), k_sum(&entries, 7, 8));
+        assert_eq!(None, k_sum(&entries, 8, 8));
+    }
+
+    #[test]
+    fn distinct_solutions() {
+        // 1 + 4 twice over is still one solution, 1 + 1 + 3 needs both ones
+        let entries = vec![1, 4, 2, 3, 1, 4, 5, 0];
+        let values = |k| -> Vec<Vec<i32>> {
+            k_sums(&entries, k, 5)
+                .iter()
+                .map(|indices| {
+                    let mut values: Vec<i32> = indices.iter().map(|i| entries[*i]).collect();
+                    values.sort_unstable();
+                    values
+                })
+                .collect()
+        };
+
+        let mut pairs = values(2);
+        pairs.sort();
+        assert_eq!(vec![vec![0, 5], vec![1, 4], vec![2, 3]], pairs);
+        assert_eq!(vec![vec![0, 1, 4], vec![0, 2, 3], vec![1, 1, 3]], values(3));
+        assert_eq!(vec![vec![0, 1, 1, 3]], values(4));
+        assert!(k_sums(&entries, 2, 100).is_empty());
     }
 }
@@ -1,3 +1,8 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use crate::error::ParseError;
+
 pub struct PolicyPass {
     letter: char,
     min: i32,
@@ -6,39 +11,401 @@ pub struct PolicyPass {
 }
 
 impl PolicyPass {
-    fn from_str(s: &str) -> Self {
-        if let Ok((min, max, letter, password)) =
-            scan_fmt!(s, "{d}-{d} {}: {}", i32, i32, char, String)
-        {
-            return PolicyPass {
-                letter,
-                min,
-                max,
-                password,
-            };
+    fn from_str(s: &str) -> Option<Self> {
+        let (min, max, letter, password) =
+            scan_fmt!(s, "{d}-{d} {}: {}", i32, i32, char, String).ok()?;
+        Some(PolicyPass {
+            letter,
+            min,
+            max,
+            password,
+        })
+    }
+
+    pub fn get_letter(&self) -> char {
+        self.letter
+    }
+
+    pub fn get_min(&self) -> i32 {
+        self.min
+    }
+
+    pub fn get_max(&self) -> i32 {
+        self.max
+    }
+
+    pub fn get_password(&self) -> &str {
+        &self.password
+    }
+
+    // whether the letter is at a 1-based position, positions outside the password never hold it
+    fn letter_at(&self, position: i32) -> bool {
+        position >= 1 && self.password.chars().nth(position as usize - 1) == Some(self.letter)
+    }
+
+    pub fn valid_p1(&self) -> bool {
+        CountRange.allows(self)
+    }
+
+    pub fn valid_p2(&self) -> bool {
+        PositionsXor.allows(self)
+    }
+
+    pub fn report(&self, policy: &dyn Policy) -> Report {
+        Report {
+            password: self.password.clone(),
+            failures: policy.failures(self),
+        }
+    }
+}
+
+// Password policies, checked against a whole line so that the count and position rules can
+// use the line's own letter and numbers. Policies combine with and, or and not.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    // the policy that wasn't met, as described by it
+    pub policy: String,
+    pub reason: String,
+}
+
+pub trait Policy {
+    fn describe(&self) -> String;
+
+    // why the line doesn't meet the policy, empty when it does
+    fn failures(&self, pass: &PolicyPass) -> Vec<Failure>;
+
+    fn allows(&self, pass: &PolicyPass) -> bool {
+        self.failures(pass).is_empty()
+    }
+
+    fn and<P: Policy + 'static>(self, other: P) -> All
+    where
+        Self: Sized + 'static,
+    {
+        All(vec![Box::new(self), Box::new(other)])
+    }
+
+    fn or<P: Policy + 'static>(self, other: P) -> Any
+    where
+        Self: Sized + 'static,
+    {
+        Any(vec![Box::new(self), Box::new(other)])
+    }
+
+    fn not(self) -> Not
+    where
+        Self: Sized + 'static,
+    {
+        Not(Box::new(self))
+    }
+}
+
+impl<P: Policy + ?Sized> Policy for Box<P> {
+    fn describe(&self) -> String {
+        (**self).describe()
+    }
+
+    fn failures(&self, pass: &PolicyPass) -> Vec<Failure> {
+        (**self).failures(pass)
+    }
+}
+
+fn failure(policy: &dyn Policy, reason: String) -> Vec<Failure> {
+    vec![Failure {
+        policy: policy.describe(),
+        reason,
+    }]
+}
+
+// part 1, the letter occurs between min and max times
+pub struct CountRange;
+
+impl Policy for CountRange {
+    fn describe(&self) -> String {
+        "count".to_string()
+    }
+
+    fn failures(&self, pass: &PolicyPass) -> Vec<Failure> {
+        let count = pass.password.matches(pass.letter).count() as i32;
+        if count >= pass.min && count <= pass.max {
+            return Vec::new();
+        }
+        let reason = format!(
+            "{} occurs {} times, not {} to {}",
+            pass.letter, count, pass.min, pass.max
+        );
+        failure(self, reason)
+    }
+}
+
+// part 2, the letter is at exactly one of the positions min and max
+pub struct PositionsXor;
+
+impl Policy for PositionsXor {
+    fn describe(&self) -> String {
+        "xor".to_string()
+    }
+
+    fn failures(&self, pass: &PolicyPass) -> Vec<Failure> {
+        match (pass.letter_at(pass.min), pass.letter_at(pass.max)) {
+            (true, false) | (false, true) => Vec::new(),
+            (true, true) => failure(
+                self,
+                format!("{} at both {} and {}", pass.letter, pass.min, pass.max),
+            ),
+            (false, false) => failure(
+                self,
+                format!("{} at neither {} nor {}", pass.letter, pass.min, pass.max),
+            ),
+        }
+    }
+}
+
+// the letter is at both of the positions min and max
+pub struct PositionsAnd;
+
+impl Policy for PositionsAnd {
+    fn describe(&self) -> String {
+        "both".to_string()
+    }
+
+    fn failures(&self, pass: &PolicyPass) -> Vec<Failure> {
+        let missing: Vec<String> = [pass.min, pass.max]
+            .iter()
+            .filter(|position| !pass.letter_at(**position))
+            .map(|position| position.to_string())
+            .collect();
+        if missing.is_empty() {
+            return Vec::new();
+        }
+        let reason = format!("{} not at {}", pass.letter, missing.join(" and "));
+        failure(self, reason)
+    }
+}
+
+// none of the substrings occur in the password
+pub struct Forbidden(pub Vec<String>);
+
+impl Policy for Forbidden {
+    fn describe(&self) -> String {
+        let quoted: Vec<String> = self.0.iter().map(|s| format!("{:?}", s)).collect();
+        format!("forbid {}", quoted.join(" "))
+    }
+
+    fn failures(&self, pass: &PolicyPass) -> Vec<Failure> {
+        let found: Vec<&String> = self
+            .0
+            .iter()
+            .filter(|s| pass.password.contains(s.as_str()))
+            .collect();
+        if found.is_empty() {
+            return Vec::new();
+        }
+        let quoted: Vec<String> = found.iter().map(|s| format!("{:?}", s)).collect();
+        failure(self, format!("contains {}", quoted.join(" and ")))
+    }
+}
+
+// Shannon entropy of the password's characters times its length, in bits
+pub fn entropy(password: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in password.chars() {
+        *counts.entry(c).or_insert(0) += 1;
+    }
+    let len = password.chars().count() as f64;
+    counts
+        .values()
+        .map(|n| {
+            let p = *n as f64 / len;
+            -p * p.log2() * len
+        })
+        .sum()
+}
+
+pub struct MinEntropy(pub f64);
+
+impl Policy for MinEntropy {
+    fn describe(&self) -> String {
+        format!("entropy {}", self.0)
+    }
+
+    fn failures(&self, pass: &PolicyPass) -> Vec<Failure> {
+        let bits = entropy(&pass.password);
+        if bits >= self.0 {
+            return Vec::new();
+        }
+        failure(
+            self,
+            format!("{:.1} bits of entropy, below {}", bits, self.0),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    Lower,
+    Upper,
+    Digit,
+    Symbol,
+}
+
+impl CharClass {
+    pub const ALL: [CharClass; 4] = [
+        CharClass::Lower,
+        CharClass::Upper,
+        CharClass::Digit,
+        CharClass::Symbol,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CharClass::Lower => "lower",
+            CharClass::Upper => "upper",
+            CharClass::Digit => "digit",
+            CharClass::Symbol => "symbol",
+        }
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            CharClass::Lower => c.is_lowercase(),
+            CharClass::Upper => c.is_uppercase(),
+            CharClass::Digit => c.is_numeric(),
+            CharClass::Symbol => !c.is_alphanumeric() && !c.is_whitespace(),
+        }
+    }
+}
+
+// characters from at least `at_least` of the classes
+pub struct Classes {
+    pub classes: Vec<CharClass>,
+    pub at_least: usize,
+}
+
+impl Policy for Classes {
+    fn describe(&self) -> String {
+        let names: Vec<&str> = self.classes.iter().map(CharClass::name).collect();
+        format!("classes {} of {}", self.at_least, names.join(" "))
+    }
+
+    fn failures(&self, pass: &PolicyPass) -> Vec<Failure> {
+        let missing: Vec<&str> = self
+            .classes
+            .iter()
+            .filter(|class| !pass.password.chars().any(|c| class.contains(c)))
+            .map(CharClass::name)
+            .collect();
+        let present = self.classes.len() - missing.len();
+        if present >= self.at_least {
+            return Vec::new();
+        }
+        let reason = format!(
+            "{} of the classes, missing {}",
+            present,
+            missing.join(" and ")
+        );
+        failure(self, reason)
+    }
+}
+
+// every policy holds, the failures of all that don't
+pub struct All(pub Vec<Box<dyn Policy>>);
+
+impl Policy for All {
+    fn describe(&self) -> String {
+        let parts: Vec<String> = self.0.iter().map(|p| p.describe()).collect();
+        format!("({})", parts.join(" and "))
+    }
+
+    fn failures(&self, pass: &PolicyPass) -> Vec<Failure> {
+        self.0.iter().flat_map(|p| p.failures(pass)).collect()
+    }
+}
+
+// at least one policy holds, otherwise a single failure with every reason
+pub struct Any(pub Vec<Box<dyn Policy>>);
+
+impl Policy for Any {
+    fn describe(&self) -> String {
+        let parts: Vec<String> = self.0.iter().map(|p| p.describe()).collect();
+        format!("({})", parts.join(" or "))
+    }
+
+    fn failures(&self, pass: &PolicyPass) -> Vec<Failure> {
+        let mut reasons = Vec::new();
+        for policy in &self.0 {
+            let failures = policy.failures(pass);
+            if failures.is_empty() {
+                return Vec::new();
+            }
+            reasons.extend(failures.into_iter().map(|f| f.reason));
         }
-        panic!("unable to parse input line")
+        failure(self, reasons.join("; "))
     }
+}
+
+pub struct Not(pub Box<dyn Policy>);
 
-    fn valid_p1(&self) -> bool {
-        let count = self.password.matches(self.letter).count() as i32;
-        count >= self.min && count <= self.max
+impl Policy for Not {
+    fn describe(&self) -> String {
+        format!("not {}", self.0.describe())
     }
 
-    fn valid_p2(&self) -> bool {
-        let letter_at_min =
-            self.password.chars().nth((self.min - 1) as usize).unwrap() == self.letter;
+    fn failures(&self, pass: &PolicyPass) -> Vec<Failure> {
+        if self.0.allows(pass) {
+            failure(self, format!("meets {}", self.0.describe()))
+        } else {
+            Vec::new()
+        }
+    }
+}
 
-        let letter_at_max =
-            self.password.chars().nth((self.max - 1) as usize).unwrap() == self.letter;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub password: String,
+    pub failures: Vec<Failure>,
+}
 
-        letter_at_min ^ letter_at_max
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.passed() {
+            return write!(f, "{}: ok", self.password);
+        }
+        let failures: Vec<String> = self
+            .failures
+            .iter()
+            .map(|failure| format!("{} failed, {}", failure.policy, failure.reason))
+            .collect();
+        write!(f, "{}: {}", self.password, failures.join("; "))
     }
 }
 
 #[aoc_generator(day2)]
 pub fn parse_input(input: &str) -> Vec<PolicyPass> {
-    input.lines().map(PolicyPass::from_str).collect()
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<Vec<PolicyPass>, ParseError> {
+    input
+        .lines()
+        .map(|line| {
+            PolicyPass::from_str(line).ok_or_else(|| {
+                ParseError::at(
+                    2,
+                    input,
+                    line,
+                    "expected `<min>-<max> <letter>: <password>`",
+                )
+            })
+        })
+        .collect()
 }
 
 #[aoc(day2, part1)]
@@ -50,3 +417,72 @@ pub fn part1(input: &[PolicyPass]) -> usize {
 pub fn part2(input: &[PolicyPass]) -> usize {
     input.iter().filter(|p| p.valid_p2()).count()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pass(line: &str) -> PolicyPass {
+        PolicyPass::from_str(line).unwrap()
+    }
+
+    #[test]
+    fn parts() {
+        let input = parse_input("1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc");
+        assert_eq!(2, part1(&input));
+        assert_eq!(1, part2(&input));
+        // position 9 is past the end of the password
+        assert!(!pass("2-9 c: abcd").valid_p2());
+        assert!(pass("1-9 a: abcd").valid_p2());
+        assert!(!pass("0-2 a: ab").valid_p2());
+    }
+
+    #[test]
+    fn policies() {
+        let line = pass("1-3 a: abcde");
+        assert!(PositionsAnd.failures(&pass("1-3 a: aba")).is_empty());
+        assert_eq!("a not at 3", PositionsAnd.failures(&line)[0].reason);
+        assert_eq!(
+            "contains \"bc\"",
+            Forbidden(vec!["bc".to_string(), "x".to_string()]).failures(&line)[0].reason
+        );
+        assert!((entropy("abcd") - 8.0).abs() < 1e-9);
+        assert_eq!(0.0, entropy("aaaa"));
+        assert!(MinEntropy(8.0).allows(&pass("1-3 a: abcd")));
+        assert!(!MinEntropy(8.0).allows(&pass("1-3 a: abca")));
+
+        let classes = Classes {
+            classes: CharClass::ALL.to_vec(),
+            at_least: 3,
+        };
+        assert!(classes.allows(&pass("1-3 a: aB3")));
+        assert_eq!(
+            "2 of the classes, missing upper and symbol",
+            classes.failures(&pass("1-3 a: a3"))[0].reason
+        );
+    }
+
+    #[test]
+    fn combinators() {
+        let line = pass("1-3 b: cdefg");
+        let policy = CountRange.and(PositionsXor.or(MinEntropy(100.0)));
+        assert_eq!("(count and (xor or entropy 100))", policy.describe());
+        let report = line.report(&policy);
+        assert!(!report.passed());
+        assert_eq!(
+            "cdefg: count failed, b occurs 0 times, not 1 to 3; \
+             (xor or entropy 100) failed, b at neither 1 nor 3; 11.6 bits of entropy, below 100",
+            report.to_string()
+        );
+
+        let policy = Forbidden(vec!["e".to_string()]).not();
+        assert_eq!("cdefg: ok", line.report(&policy).to_string());
+        assert_eq!(
+            vec![Failure {
+                policy: "not forbid \"e\"".to_string(),
+                reason: "meets forbid \"e\"".to_string(),
+            }],
+            policy.failures(&pass("1-3 b: cd"))
+        );
+    }
+}
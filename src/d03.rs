@@ -1,83 +1,228 @@
-use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
 
-#[derive(Debug, Eq, PartialEq, Hash)]
-struct Coord {
-    x: usize,
-    y: usize,
-}
+use crate::error::ParseError;
+use crate::grid::Grid;
 
 pub struct Forest {
-    width: usize,
-    height: usize,
-    trees: HashSet<Coord>,
+    trees: Grid<bool>,
 }
 
 impl Forest {
-    fn new(input: &str) -> Self {
-        let width = input.lines().last().unwrap().len();
-        let mut height = 0;
-        let mut trees = HashSet::new();
-        for line in input.lines() {
-            height += 1;
-
-            for (x, c) in line.chars().enumerate() {
-                match c {
-                    '#' => {
-                        trees.insert(Coord { x, y: height - 1 });
-                    }
-                    '.' => {}
-                    _ => panic!("unknown character"),
-                }
-            }
+    fn new(input: &str) -> Result<Self, ParseError> {
+        let trees = Grid::parse(3, input, |c| match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        })?;
+        if trees.width() == 0 {
+            return Err(ParseError::input(3, "empty map"));
         }
 
-        Forest {
-            width,
-            height,
-            trees,
-        }
+        Ok(Forest { trees })
+    }
+
+    pub fn width(&self) -> usize {
+        self.trees.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.trees.height()
+    }
+
+    // the map repeats to the right, and to the left for slopes that go that way
+    pub fn is_tree(&self, x: i64, y: usize) -> bool {
+        *self.trees.get_wrapped(x as isize, y as isize)
+    }
+
+    // Every tree on the way down from the top row at column start, as (x, y) on the map with x
+    // wrapped into its width. The toboggan stops every `down` rows, `right` columns further on,
+    // so 2/2 only looks at every other row, use the reduced slope to stop on every row.
+    pub fn trees_hit(&self, start: i64, slope: Slope) -> Vec<(usize, usize)> {
+        let width = self.trees.width() as i64;
+        (0..self.trees.height())
+            .step_by(slope.down)
+            .enumerate()
+            .map(|(k, y)| (start + k as i64 * slope.right, y))
+            .filter(|(x, y)| self.is_tree(*x, *y))
+            .map(|(x, y)| (x.rem_euclid(width) as usize, y))
+            .collect()
+    }
+
+    pub fn count_trees(&self, start: i64, slope: Slope) -> usize {
+        self.trees_hit(start, slope).len()
+    }
+
+    // the number of trees hit with each slope
+    pub fn survey(&self, start: i64, slopes: &[Slope]) -> Vec<(Slope, usize)> {
+        slopes
+            .iter()
+            .map(|slope| (*slope, self.count_trees(start, *slope)))
+            .collect()
+    }
+
+    // the first of the slopes that hits the fewest trees
+    pub fn fewest_trees(&self, start: i64, slopes: &[Slope]) -> Option<(Slope, usize)> {
+        self.survey(start, slopes)
+            .into_iter()
+            .min_by_key(|(_, trees)| *trees)
+    }
+
+    // the first of the slopes that hits the most trees
+    pub fn most_trees(&self, start: i64, slopes: &[Slope]) -> Option<(Slope, usize)> {
+        self.survey(start, slopes)
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, trees)| *trees)
+    }
+}
+
+// a step of right columns and down rows, down is never 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Slope {
+    right: i64,
+    down: usize,
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Slope {
+    pub fn new(right: i64, down: usize) -> Self {
+        assert!(down > 0, "a slope has to go down");
+        Slope { right, down }
     }
 
-    fn is_tree(&self, x: usize, y: usize) -> bool {
-        let coord = Coord {
-            x: x % self.width,
-            y,
-        };
-        self.trees.contains(&coord)
+    // the same ratio with the smallest step, 6/2 is 3/1
+    pub fn reduced(&self) -> Self {
+        let divisor = gcd(self.right.unsigned_abs(), self.down as u64);
+        Slope {
+            right: self.right / divisor as i64,
+            down: self.down / divisor as usize,
+        }
     }
 
-    fn count_hit_trees(&self, vx: usize, vy: usize) -> usize {
-        let mut count: usize = 0;
+    pub fn right(&self) -> i64 {
+        self.right
+    }
 
-        let mut x: usize = 0;
-        let mut y: usize = 0;
+    pub fn down(&self) -> usize {
+        self.down
+    }
 
-        while y < self.height {
-            if self.is_tree(x, y) {
-                count += 1;
+    // Every step in the ranges, ordered by down then right. Steps with the same ratio are all
+    // there: 2/2 stops on other rows than 1/1, see Forest::trees_hit.
+    pub fn within(rights: RangeInclusive<i64>, downs: RangeInclusive<usize>) -> Vec<Slope> {
+        let mut slopes = Vec::new();
+        for down in downs.filter(|down| *down > 0) {
+            for right in rights.clone() {
+                slopes.push(Slope::new(right, down));
             }
-            x += vx;
-            y += vy;
         }
-        count
+        slopes
+    }
+}
+
+impl Display for Slope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad(&format!("{}/{}", self.right, self.down))
     }
 }
 
 #[aoc_generator(day3)]
 pub fn parse_input(input: &str) -> Forest {
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<Forest, ParseError> {
     Forest::new(input)
 }
 
 #[aoc(day3, part1)]
 pub fn part1(forest: &Forest) -> usize {
-    forest.count_hit_trees(3, 1)
+    forest.count_trees(0, Slope::new(3, 1))
 }
 
 #[aoc(day3, part2)]
 pub fn part2(forest: &Forest) -> usize {
-    forest.count_hit_trees(1, 1)
-        * forest.count_hit_trees(3, 1)
-        * forest.count_hit_trees(5, 1)
-        * forest.count_hit_trees(7, 1)
-        * forest.count_hit_trees(1, 2)
+    let slopes = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+    slopes
+        .iter()
+        .map(|(right, down)| forest.count_trees(0, Slope::new(*right, *down)))
+        .product()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
+
+    #[test]
+    fn parts() {
+        let forest = parse_input(EXAMPLE);
+        assert_eq!(7, part1(&forest));
+        assert_eq!(336, part2(&forest));
+    }
+
+    #[test]
+    fn routes() {
+        let forest = parse_input(EXAMPLE);
+        let hit = forest.trees_hit(0, Slope::new(3, 1));
+        assert_eq!(
+            vec![(6, 2), (1, 4), (4, 5), (10, 7), (2, 8), (5, 9), (8, 10)],
+            hit
+        );
+
+        // 2/2 stops on every other row like a (2, 2) step always has, 1/1 on every row
+        assert_eq!(vec![(10, 10)], forest.trees_hit(0, Slope::new(2, 2)));
+        assert_eq!(
+            vec![(5, 5), (10, 10)],
+            forest.trees_hit(0, Slope::new(1, 1))
+        );
+        assert_eq!(Slope::new(1, 1), Slope::new(2, 2).reduced());
+        assert_eq!(Slope::new(3, 1), Slope::new(6, 2).reduced());
+        assert_ne!(Slope::new(3, 1), Slope::new(6, 2));
+        assert_eq!("-1/2", Slope::new(-2, 4).reduced().to_string());
+        assert_eq!("-2/4", Slope::new(-2, 4).to_string());
+        // starting a map width over is the same route
+        assert_eq!(hit, forest.trees_hit(11, Slope::new(3, 1)));
+        assert_eq!(hit, forest.trees_hit(-11, Slope::new(3, 1)));
+        assert_eq!(vec![(10, 3)], forest.trees_hit(0, Slope::new(-1, 3)));
+        assert_eq!(4, forest.count_trees(2, Slope::new(0, 1)));
+    }
+
+    #[test]
+    fn families() {
+        let slopes = Slope::within(0..=2, 1..=2);
+        let steps: Vec<_> = slopes.iter().map(|s| (s.right(), s.down())).collect();
+        assert_eq!(vec![(0, 1), (1, 1), (2, 1), (0, 2), (1, 2), (2, 2)], steps);
+
+        let forest = parse_input(EXAMPLE);
+        let slopes = Slope::within(1..=7, 1..=2);
+        let survey = forest.survey(0, &slopes);
+        assert_eq!(14, survey.len());
+        assert_eq!((Slope::new(3, 1), 7), survey[2]);
+        // the same ratio, different rows
+        assert_eq!((Slope::new(1, 1), 2), survey[0]);
+        assert_eq!((Slope::new(2, 2), 1), survey[8]);
+        assert_eq!(Some((Slope::new(5, 2), 0)), forest.fewest_trees(0, &slopes));
+        assert_eq!(Some((Slope::new(3, 1), 7)), forest.most_trees(0, &slopes));
+        assert_eq!(None, forest.most_trees(0, &[]));
+    }
 }
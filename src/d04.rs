@@ -1,5 +1,7 @@
 use fancy_regex::Regex;
 
+use crate::error::ParseError;
+
 #[derive(Debug, Default)]
 pub struct Passport {
     byr: Option<String>,
@@ -12,27 +14,25 @@ pub struct Passport {
 }
 
 impl Passport {
-    fn from_str(s: &str) -> Self {
+    // on error, returns the offending part of s and what is wrong with it
+    fn from_str(s: &str) -> Result<Self, (&str, &'static str)> {
         let mut passport: Passport = Default::default();
-        for kv in s.split(&[' ', '\n'][..]) {
-            if let Ok((key, val)) = scan_fmt!(kv, "{}:{}", String, String) {
-                let key = key.as_str();
-                match key {
-                    "byr" => passport.byr = Some(val),
-                    "iyr" => passport.iyr = Some(val),
-                    "eyr" => passport.eyr = Some(val),
-                    "hgt" => passport.hgt = Some(val),
-                    "hcl" => passport.hcl = Some(val),
-                    "ecl" => passport.ecl = Some(val),
-                    "pid" => passport.pid = Some(val),
-                    "cid" => {}
-                    _ => {
-                        panic!("unknown key");
-                    }
-                }
+        for kv in s.split(&[' ', '\n'][..]).filter(|kv| !kv.is_empty()) {
+            let colon = kv.find(':').ok_or((kv, "expected `key:value`"))?;
+            let (key, val) = (&kv[..colon], kv[colon + 1..].to_string());
+            match key {
+                "byr" => passport.byr = Some(val),
+                "iyr" => passport.iyr = Some(val),
+                "eyr" => passport.eyr = Some(val),
+                "hgt" => passport.hgt = Some(val),
+                "hcl" => passport.hcl = Some(val),
+                "ecl" => passport.ecl = Some(val),
+                "pid" => passport.pid = Some(val),
+                "cid" => {}
+                _ => return Err((key, "unknown key")),
             }
         }
-        passport
+        Ok(passport)
     }
 
     fn valid_p1(&self) -> bool {
@@ -139,7 +139,16 @@ impl Passport {
 
 #[aoc_generator(day4)]
 pub fn parse_input(input: &str) -> Vec<Passport> {
-    input.split("\n\n").map(Passport::from_str).collect()
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<Vec<Passport>, ParseError> {
+    input
+        .split("\n\n")
+        .map(|s| {
+            Passport::from_str(s).map_err(|(text, message)| ParseError::at(4, input, text, message))
+        })
+        .collect()
 }
 
 #[aoc(day4, part1)]
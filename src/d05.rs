@@ -1,14 +1,16 @@
 use fancy_regex::Regex;
 
+use crate::error::ParseError;
+
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct SeatId(usize);
 
 const RE_BOARDING_PASS: &str = r"^([BF]{7})([LR]{3})$";
 
 impl SeatId {
-    fn from_str(s: &str) -> Self {
+    fn from_str(s: &str) -> Option<Self> {
         let re = Regex::new(RE_BOARDING_PASS).unwrap();
-        let captures = re.captures(s).unwrap().unwrap();
+        let captures = re.captures(s).unwrap()?;
         let row_str = captures.get(1).unwrap().as_str();
         let col_str = captures.get(2).unwrap().as_str();
 
@@ -40,13 +42,23 @@ impl SeatId {
         )
         .unwrap();
 
-        SeatId((row * 8) + col)
+        Some(SeatId((row * 8) + col))
     }
 }
 
 #[aoc_generator(day5)]
 pub fn parse_input(input: &str) -> Vec<SeatId> {
-    input.lines().map(SeatId::from_str).collect()
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<Vec<SeatId>, ParseError> {
+    input
+        .lines()
+        .map(|line| {
+            SeatId::from_str(line)
+                .ok_or_else(|| ParseError::at(5, input, line, "expected 7 of F/B then 3 of L/R"))
+        })
+        .collect()
 }
 
 #[aoc(day5, part1)]
@@ -74,8 +86,8 @@ mod tests {
 
     #[test]
     fn test_seat_id() {
-        assert_eq!(SeatId(567), SeatId::from_str("BFFFBBFRRR"));
-        assert_eq!(SeatId(119), SeatId::from_str("FFFBBBFRRR"));
-        assert_eq!(SeatId(820), SeatId::from_str("BBFFBBFRLL"));
+        assert_eq!(Some(SeatId(567)), SeatId::from_str("BFFFBBFRRR"));
+        assert_eq!(Some(SeatId(119)), SeatId::from_str("FFFBBBFRRR"));
+        assert_eq!(Some(SeatId(820)), SeatId::from_str("BBFFBBFRLL"));
     }
 }
@@ -1,15 +1,20 @@
 use std::collections::{HashMap, HashSet};
 
+use crate::error::{char_at, ParseError};
+
 #[derive(Debug)]
 pub struct Group {
     forms: Vec<Form>,
 }
 
 impl Group {
-    fn from_str(s: &str) -> Self {
-        Group {
-            forms: s.split('\n').map(Form::from_str).collect(),
-        }
+    fn from_str(s: &str) -> Result<Self, &str> {
+        Ok(Group {
+            forms: s
+                .split('\n')
+                .map(Form::from_str)
+                .collect::<Result<_, _>>()?,
+        })
     }
 }
 
@@ -19,16 +24,30 @@ pub struct Form {
 }
 
 impl Form {
-    fn from_str(s: &str) -> Self {
-        Form {
-            yes_questions: s.chars().collect(),
+    // on error, returns the offending character
+    fn from_str(s: &str) -> Result<Self, &str> {
+        if let Some(i) = s.find(|c: char| !c.is_ascii_lowercase()) {
+            return Err(char_at(s, i));
         }
+        Ok(Form {
+            yes_questions: s.chars().collect(),
+        })
     }
 }
 
 #[aoc_generator(day6)]
 pub fn parse_input(input: &str) -> Vec<Group> {
-    input.split("\n\n").map(Group::from_str).collect()
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<Vec<Group>, ParseError> {
+    input
+        .split("\n\n")
+        .map(|s| {
+            Group::from_str(s)
+                .map_err(|text| ParseError::at(6, input, text, "expected a question from a-z"))
+        })
+        .collect()
 }
 
 #[aoc(day6, part1)]
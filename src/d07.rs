@@ -2,6 +2,8 @@ use std::collections::{HashMap, HashSet};
 
 use fancy_regex::Regex;
 
+use crate::error::ParseError;
+
 type BagName = String;
 
 #[derive(Debug)]
@@ -10,14 +12,16 @@ pub struct RuleSet {
 }
 
 impl RuleSet {
-    fn from_str(input: &str) -> Self {
+    fn from_str(input: &str) -> Result<Self, ParseError> {
         let mut rules = HashMap::new();
 
         let re = Regex::new(r"^(.*) bags contain (.*)\.$").unwrap();
         let content_re = Regex::new(r"^(\d+) (.*) bag[s]?$").unwrap();
 
-        input.lines().for_each(|line| {
-            let captures = re.captures(line).unwrap().unwrap();
+        for line in input.lines() {
+            let captures = re.captures(line).unwrap().ok_or_else(|| {
+                ParseError::at(7, input, line, "expected `<bag> bags contain <contents>.`")
+            })?;
             let bag_name: BagName = captures.get(1).unwrap().as_str().to_string();
 
             let contents_string = captures.get(2).unwrap().as_str();
@@ -26,22 +30,25 @@ impl RuleSet {
             if contents_string != "no other bags" {
                 for content_string in contents_string.split(',') {
                     let content_string = content_string.trim();
-                    let content_captures = content_re.captures(content_string).unwrap().unwrap();
+                    let content_captures = content_re
+                        .captures(content_string)
+                        .unwrap()
+                        .ok_or_else(|| {
+                            ParseError::at(7, input, content_string, "expected `<n> <bag> bags`")
+                        })?;
+                    let count = content_captures.get(1).unwrap().as_str();
                     let bag_content = BagContent {
                         name: String::from(content_captures.get(2).unwrap().as_str()),
-                        count: content_captures
-                            .get(1)
-                            .unwrap()
-                            .as_str()
+                        count: count
                             .parse::<usize>()
-                            .unwrap(),
+                            .map_err(|_| ParseError::at(7, input, count, "count out of range"))?,
                     };
                     contents.push(bag_content);
                 }
             }
             rules.insert(bag_name, contents);
-        });
-        RuleSet { rules }
+        }
+        Ok(RuleSet { rules })
     }
 
     fn count_bags_containing(&self, search_key: &str) -> usize {
@@ -85,6 +92,10 @@ pub struct BagContent {
 
 #[aoc_generator(day7)]
 pub fn parse_input(input: &str) -> RuleSet {
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<RuleSet, ParseError> {
     RuleSet::from_str(input)
 }
 
@@ -1,13 +1,38 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
+use crate::error::ParseError;
+
+pub mod analysis;
+pub mod asm;
+pub mod debugger;
+pub mod fast;
+pub mod history;
+pub mod scheduler;
+
+use history::Journal;
+
+pub const REGISTERS: usize = 4;
+pub const MEMORY_WORDS: usize = 1 << 16;
+
 #[derive(Debug, PartialEq)]
 pub struct Cpu {
     state: CpuState,
     ins_ptr: usize,
     instructions: Vec<Instruction>,
     acc_register: i64,
+    registers: [i64; REGISTERS],
+    memory: Memory,
+    output: Vec<i64>,
+    // (channel, value) pairs sent but not yet collected by a scheduler
+    sent: Vec<(i64, i64)>,
+    detect_loops: bool,
     visited_instructions: HashSet<usize>,
+    // visited instructions in execution order, only kept while detecting loops
+    path: Vec<usize>,
+    journal: Journal,
 }
 
 impl Cpu {
@@ -17,42 +42,167 @@ impl Cpu {
             ins_ptr: 0,
             instructions,
             acc_register: 0,
+            registers: [0; REGISTERS],
+            memory: Memory::default(),
+            output: Vec::new(),
+            sent: Vec::new(),
+            detect_loops: true,
             visited_instructions: HashSet::new(),
+            path: Vec::new(),
+            journal: Journal::default(),
         }
     }
 
+    // programs using conditional jumps can legitimately revisit instructions,
+    // so the run-once rule can be turned off for them
+    pub fn set_loop_detection(&mut self, enabled: bool) {
+        self.detect_loops = enabled;
+    }
+
     pub fn get_acc(&self) -> i64 {
         self.acc_register
     }
 
+    pub fn get_register(&self, register: usize) -> Option<i64> {
+        self.registers.get(register).copied()
+    }
+
+    pub fn get_memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn get_output(&self) -> &[i64] {
+        &self.output
+    }
+
+    pub fn take_sent(&mut self) -> Vec<(i64, i64)> {
+        std::mem::take(&mut self.sent)
+    }
+
+    // completes a rcv the Cpu is blocked on, returns false if it wasn't waiting
+    pub fn deliver(&mut self, value: i64) -> bool {
+        if !matches!(self.state, CpuState::Blocked { .. }) {
+            return false;
+        }
+
+        if let Some(undo) = self.undo_entry() {
+            self.journal.push(undo);
+        }
+        self.acc_register = value;
+        self.ins_ptr += 1;
+        self.state = CpuState::Ready;
+        true
+    }
+
+    pub fn get_ins_ptr(&self) -> usize {
+        self.ins_ptr
+    }
+
+    pub fn get_state(&self) -> &CpuState {
+        &self.state
+    }
+
+    pub fn get_instruction(&self) -> Option<&Instruction> {
+        self.instructions.get(self.ins_ptr)
+    }
+
     pub fn step(&mut self) -> &CpuState {
+        let undo = self.undo_entry();
+        self.advance();
+        if let Some(undo) = undo {
+            self.journal.push(undo);
+        }
+        &self.state
+    }
+
+    // true once the program has run off the end at exactly its last instruction + 1
+    pub fn is_clean_exit(&self) -> bool {
+        self.state
+            == CpuState::Halted {
+                exit_ptr: self.instructions.len(),
+            }
+    }
+
+    fn advance(&mut self) {
+        if self.state != CpuState::Ready {
+            return;
+        }
+
         // out of instructions is normal termination
         if self.ins_ptr >= self.instructions.len() {
-            self.state = CpuState::Halted;
-            return &self.state;
+            self.state = CpuState::Halted {
+                exit_ptr: self.ins_ptr,
+            };
+            return;
         }
 
-        let instruction = self.instructions.get(self.ins_ptr).unwrap();
+        let instruction = self.instructions[self.ins_ptr];
 
-        // error if you try to run the same instruction more than once
-        if !self.visited_instructions.insert(self.ins_ptr) {
-            self.state = CpuState::Error;
+        // running the same instruction more than once is an infinite loop
+        if self.detect_loops {
+            if self.visited_instructions.insert(self.ins_ptr) {
+                self.path.push(self.ins_ptr);
+            } else {
+                let start = self.path.iter().position(|i| *i == self.ins_ptr).unwrap();
+                self.state = CpuState::InfiniteLoop {
+                    entry: self.ins_ptr,
+                    body: self.path[start..].to_vec(),
+                };
+                return;
+            }
         }
 
-        if self.state != CpuState::Ready {
-            return &self.state;
+        // rcv waits for a value to be delivered before moving on
+        if instruction.op == OpCode::Rcv {
+            self.state = CpuState::Blocked {
+                channel: instruction.val,
+            };
+            return;
         }
 
-        match &instruction.op {
-            OpCode::Acc => {
-                self.acc_register += instruction.val;
-                self.ins_ptr += 1;
+        match self.execute(instruction) {
+            Some(next) if next < 0 => {
+                self.state = CpuState::JumpOutOfBounds {
+                    from: self.ins_ptr,
+                    target: next,
+                }
+            }
+            Some(next) => self.ins_ptr = next as usize,
+            None => {
+                self.state = CpuState::Fault {
+                    at: self.ins_ptr,
+                    instruction,
+                }
             }
-            OpCode::Jmp => self.ins_ptr = (self.ins_ptr as i64 + instruction.val) as usize,
-            OpCode::Nop => self.ins_ptr += 1,
         }
+    }
 
-        &self.state
+    // returns the next instruction pointer, or None if the instruction faulted
+    fn execute(&mut self, instruction: Instruction) -> Option<i64> {
+        let val = instruction.val;
+        let jump = self.ins_ptr as i64 + val;
+
+        match instruction.op {
+            OpCode::Acc => self.acc_register = self.acc_register.checked_add(val)?,
+            OpCode::Sub => self.acc_register = self.acc_register.checked_sub(val)?,
+            OpCode::Mul => self.acc_register = self.acc_register.checked_mul(val)?,
+            OpCode::Mod => self.acc_register = self.acc_register.checked_rem_euclid(val)?,
+            OpCode::Jmp => return Some(jump),
+            OpCode::Jz if self.acc_register == 0 => return Some(jump),
+            OpCode::Jnz if self.acc_register != 0 => return Some(jump),
+            OpCode::Jz | OpCode::Jnz | OpCode::Nop => {}
+            OpCode::Ld => self.acc_register = self.memory.read(Memory::address(val)?),
+            OpCode::St => self.memory.write(Memory::address(val)?, self.acc_register),
+            OpCode::Swp => {
+                let register = self.registers.get_mut(usize::try_from(val).ok()?)?;
+                std::mem::swap(&mut self.acc_register, register);
+            }
+            OpCode::Out => self.output.push(self.acc_register),
+            OpCode::Snd => self.sent.push((val, self.acc_register)),
+            OpCode::Rcv => unreachable!("rcv is completed by deliver"),
+        }
+
+        Some(self.ins_ptr as i64 + 1)
     }
 
     pub fn run(&mut self) -> &CpuState {
@@ -63,11 +213,43 @@ impl Cpu {
     }
 }
 
-#[derive(Debug, PartialEq)]
+// sparse word-addressed memory, unwritten words read as zero
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct Memory {
+    words: HashMap<usize, i64>,
+}
+
+impl Memory {
+    fn address(val: i64) -> Option<usize> {
+        usize::try_from(val).ok().filter(|a| *a < MEMORY_WORDS)
+    }
+
+    pub fn read(&self, address: usize) -> i64 {
+        self.words.get(&address).copied().unwrap_or(0)
+    }
+
+    pub fn write(&mut self, address: usize, value: i64) {
+        if value == 0 {
+            self.words.remove(&address);
+        } else {
+            self.words.insert(address, value);
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum CpuState {
     Ready,
-    Halted,
-    Error,
+    // exit_ptr is the instruction pointer the program left through, anything past
+    // instructions.len() means a jump overshot the end
+    Halted { exit_ptr: usize },
+    // body lists the instructions of the cycle in execution order, starting with entry
+    InfiniteLoop { entry: usize, body: Vec<usize> },
+    JumpOutOfBounds { from: usize, target: i64 },
+    // arithmetic overflow, division by zero or a bad register/memory operand
+    Fault { at: usize, instruction: Instruction },
+    // waiting on a rcv, resumed by Cpu::deliver
+    Blocked { channel: i64 },
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -76,38 +258,124 @@ pub struct Instruction {
     pub val: i64,
 }
 
+impl Instruction {
+    // `<op> <number>`, on error returns the offending part of s and what is wrong with it
+    fn from_line(s: &str) -> Result<Self, (&str, &'static str)> {
+        let mut splits = s.splitn(2, ' ');
+        let op = splits.next().unwrap_or(s);
+        let op = op.parse::<OpCode>().map_err(|_| (op, "unknown opcode"))?;
+        let val = splits.next().ok_or((s, "missing argument"))?;
+        let val = val
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| (val, "expected a number"))?;
+        Ok(Instruction { op, val })
+    }
+}
+
 impl FromStr for Instruction {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let splits: Vec<&str> = s.split(' ').collect();
-        let op = OpCode::from_str(splits.get(0).unwrap());
-        let val = splits.get(1).unwrap().trim().parse::<i64>().unwrap();
-        Ok(Instruction { op, val })
+        Instruction::from_line(s).map_err(|(text, message)| ParseError::at(8, s, text, message))
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:+}", self.op, self.val)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum OpCode {
     Acc,
     Jmp,
     Nop,
+    Sub,
+    Mul,
+    Mod,
+    Jz,
+    Jnz,
+    Ld,
+    St,
+    Swp,
+    Out,
+    Snd,
+    Rcv,
 }
 
 impl OpCode {
-    fn from_str(s: &str) -> Self {
+    // ops whose operand is a relative instruction offset
+    pub fn is_jump(&self) -> bool {
+        matches!(self, OpCode::Jmp | OpCode::Jz | OpCode::Jnz)
+    }
+
+    // ops whose operand is a channel
+    pub fn is_channel(&self) -> bool {
+        matches!(self, OpCode::Snd | OpCode::Rcv)
+    }
+}
+
+impl FromStr for OpCode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "acc" => OpCode::Acc,
-            "jmp" => OpCode::Jmp,
-            "nop" => OpCode::Nop,
-            _ => panic!("invalid opcode"),
+            "acc" => Ok(OpCode::Acc),
+            "jmp" => Ok(OpCode::Jmp),
+            "nop" => Ok(OpCode::Nop),
+            "sub" => Ok(OpCode::Sub),
+            "mul" => Ok(OpCode::Mul),
+            "mod" => Ok(OpCode::Mod),
+            "jz" => Ok(OpCode::Jz),
+            "jnz" => Ok(OpCode::Jnz),
+            "ld" => Ok(OpCode::Ld),
+            "st" => Ok(OpCode::St),
+            "swp" => Ok(OpCode::Swp),
+            "out" => Ok(OpCode::Out),
+            "snd" => Ok(OpCode::Snd),
+            "rcv" => Ok(OpCode::Rcv),
+            _ => Err(()),
         }
     }
 }
 
+impl Display for OpCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mnemonic = match self {
+            OpCode::Acc => "acc",
+            OpCode::Jmp => "jmp",
+            OpCode::Nop => "nop",
+            OpCode::Sub => "sub",
+            OpCode::Mul => "mul",
+            OpCode::Mod => "mod",
+            OpCode::Jz => "jz",
+            OpCode::Jnz => "jnz",
+            OpCode::Ld => "ld",
+            OpCode::St => "st",
+            OpCode::Swp => "swp",
+            OpCode::Out => "out",
+            OpCode::Snd => "snd",
+            OpCode::Rcv => "rcv",
+        };
+        write!(f, "{}", mnemonic)
+    }
+}
+
 #[aoc_generator(day8)]
 pub fn parse_input(input: &str) -> Vec<Instruction> {
-    input.lines().map(|x| x.parse::<Instruction>().unwrap()).collect()
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    input
+        .lines()
+        .map(|line| {
+            Instruction::from_line(line)
+                .map_err(|(text, message)| ParseError::at(8, input, text, message))
+        })
+        .collect()
 }
 
 #[aoc(day8, part1)]
@@ -119,31 +387,140 @@ pub fn part1(instructions: &[Instruction]) -> i64 {
 
 #[aoc(day8, part2)]
 pub fn part2(instructions: &[Instruction]) -> i64 {
-    for i in 0..instructions.len() {
-        let mut new_instructions = instructions.to_owned();
-        let ins_at = new_instructions.get(i).unwrap();
-        if ins_at.op == OpCode::Jmp {
-            let new_instruction = Instruction {
-                op: OpCode::Nop,
-                val: ins_at.val,
-            };
-            new_instructions.remove(i);
-            new_instructions.insert(i, new_instruction);
-        } else if ins_at.op == OpCode::Nop {
-            let new_instruction = Instruction {
-                op: OpCode::Jmp,
-                val: ins_at.val,
-            };
-            new_instructions.remove(i);
-            new_instructions.insert(i, new_instruction);
-        }
+    let mut instructions = instructions.to_owned();
+    let repair = analysis::repair(&instructions).expect("answer not found");
+    repair.apply(&mut instructions);
+
+    let mut cpu = Cpu::new(instructions);
+    cpu.run();
+    cpu.get_acc()
+}
+
+// the example from the puzzle, shared by the tests of the submodules
+#[cfg(test)]
+pub(crate) const SAMPLE_1: &str = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn p1_t1() {
+        assert_eq!(5, part1(&parse_input(SAMPLE_1)));
+    }
+
+    #[test]
+    fn p2_t1() {
+        assert_eq!(8, part2(&parse_input(SAMPLE_1)));
+    }
+
+    #[test]
+    fn extended_opcodes() {
+        let mut cpu = Cpu::new(parse_input(SAMPLE_2));
+        assert_eq!(&CpuState::Halted { exit_ptr: 8 }, cpu.run());
+        assert!(cpu.is_clean_exit());
+        assert_eq!(&[2, 42][..], cpu.get_output());
+        assert_eq!(Some(2), cpu.get_register(1));
+        assert_eq!(42, cpu.get_memory().read(3));
+        assert_eq!(0, cpu.get_memory().read(4));
+    }
 
-        let mut new_cpu = Cpu::new(new_instructions);
-        let res = new_cpu.run();
+    #[test]
+    fn conditional_loop() {
+        let mut cpu = Cpu::new(parse_input(SAMPLE_3));
+        assert_eq!(
+            &CpuState::InfiniteLoop {
+                entry: 1,
+                body: vec![1, 2, 3]
+            },
+            cpu.run()
+        );
 
-        if res == &CpuState::Halted {
-            return new_cpu.get_acc();
+        let mut cpu = Cpu::new(parse_input(SAMPLE_3));
+        cpu.set_loop_detection(false);
+        assert_eq!(&CpuState::Halted { exit_ptr: 4 }, cpu.run());
+        assert_eq!(&[3, 2, 1][..], cpu.get_output());
+    }
+
+    #[test]
+    fn loop_diagnostics() {
+        let mut cpu = Cpu::new(parse_input(SAMPLE_1));
+        assert_eq!(
+            &CpuState::InfiniteLoop {
+                entry: 1,
+                body: vec![1, 2, 6, 7, 3, 4]
+            },
+            cpu.run()
+        );
+    }
+
+    #[test]
+    fn exits() {
+        let mut cpu = Cpu::new(parse_input("nop +0\njmp -2"));
+        assert_eq!(
+            &CpuState::JumpOutOfBounds {
+                from: 1,
+                target: -1
+            },
+            cpu.run()
+        );
+
+        let mut cpu = Cpu::new(parse_input("nop +0\njmp +5\nacc +1"));
+        assert_eq!(&CpuState::Halted { exit_ptr: 6 }, cpu.run());
+        assert!(!cpu.is_clean_exit());
+    }
+
+    #[test]
+    fn faults() {
+        for program in &["acc +1\nmod +0", "ld -1", "swp +4", "st +65536"] {
+            let mut cpu = Cpu::new(parse_input(program));
+            let at = program.lines().count() - 1;
+            match cpu.run() {
+                CpuState::Fault { at: fault_at, .. } => assert_eq!(at, *fault_at),
+                state => panic!("{}: {:?}", program, state),
+            }
+        }
+    }
+
+    #[test]
+    fn instructions() {
+        assert_eq!(
+            Ok(Instruction {
+                op: OpCode::Jz,
+                val: -3
+            }),
+            "jz -3".parse()
+        );
+        for line in &["hcf +0", "acc", "acc +1 x"] {
+            let error = line.parse::<Instruction>().unwrap_err();
+            assert_eq!(error, try_parse_input(line).unwrap_err(), "{}", line);
         }
+        let error = "acc +1 x".parse::<Instruction>().unwrap_err();
+        assert_eq!(
+            "day 8, line 1, column 5: expected a number `+1 x`",
+            error.to_string()
+        );
     }
-    panic!("answer not found");
+
+    const SAMPLE_2: &str = "acc +7
+mul +6
+st +3
+mod +5
+out +0
+swp +1
+ld +3
+out +0";
+
+    const SAMPLE_3: &str = "acc +3
+out +0
+sub +1
+jnz -2";
 }
@@ -0,0 +1,251 @@
+use super::{Cpu, CpuState, Instruction, OpCode};
+
+// Static control flow analysis of boot code.
+//
+// Without conditional jumps every instruction has exactly one successor, so the program is
+// a functional graph: each node either leads out of the program or into a cycle. Walking the
+// predecessor edges backwards from the exit finds every instruction that terminates, after
+// which a single pass over the executed path finds the jmp/nop flips that escape the loop.
+// Both passes are linear in the number of instructions.
+//
+// jz/jnz have two successors and the analysis can't know which one is taken, so for
+// programs using them `terminates` means "may terminate" and repairs are only candidates.
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Edge {
+    To(usize),
+    // jumped to or past the end of the program, normal termination
+    Halt,
+    // jumped before the start of the program, see CpuState::JumpOutOfBounds
+    OutOfBounds(i64),
+}
+
+fn edge_to(target: i64, len: usize) -> Edge {
+    if target < 0 {
+        Edge::OutOfBounds(target)
+    } else if target as usize >= len {
+        Edge::Halt
+    } else {
+        Edge::To(target as usize)
+    }
+}
+
+// the fall through (or unconditional jump) edge, plus the taken edge of a conditional jump
+fn edges(index: usize, op: OpCode, val: i64, len: usize) -> (Edge, Option<Edge>) {
+    let next = edge_to(index as i64 + 1, len);
+    let jump = edge_to(index as i64 + val, len);
+    match op {
+        OpCode::Jmp => (jump, None),
+        OpCode::Jz | OpCode::Jnz => (next, Some(jump)),
+        _ => (next, None),
+    }
+}
+
+fn flip(op: OpCode) -> Option<OpCode> {
+    match op {
+        OpCode::Jmp => Some(OpCode::Nop),
+        OpCode::Nop => Some(OpCode::Jmp),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Repair {
+    pub index: usize,
+    pub from: OpCode,
+    pub to: OpCode,
+}
+
+impl Repair {
+    pub fn apply(&self, instructions: &mut [Instruction]) {
+        let instruction = &mut instructions[self.index];
+        assert_eq!(self.from, instruction.op, "repair does not match program");
+        instruction.op = self.to;
+    }
+}
+
+pub struct ControlFlowGraph<'a> {
+    instructions: &'a [Instruction],
+    edges: Vec<(Edge, Option<Edge>)>,
+}
+
+impl<'a> ControlFlowGraph<'a> {
+    pub fn new(instructions: &'a [Instruction]) -> Self {
+        let len = instructions.len();
+        let edges = instructions
+            .iter()
+            .enumerate()
+            .map(|(i, ins)| edges(i, ins.op, ins.val, len))
+            .collect();
+
+        ControlFlowGraph {
+            instructions,
+            edges,
+        }
+    }
+
+    pub fn successors(&self, index: usize) -> impl Iterator<Item = Edge> {
+        let (edge, branch) = self.edges[index];
+        std::iter::once(edge).chain(branch)
+    }
+
+    // predecessor lists packed into one buffer, preds[offsets[i]..offsets[i + 1]]
+    fn predecessors(&self) -> (Vec<usize>, Vec<usize>) {
+        let len = self.edges.len();
+        let mut offsets = vec![0; len + 1];
+        for i in 0..len {
+            for edge in self.successors(i) {
+                if let Edge::To(target) = edge {
+                    offsets[target + 1] += 1;
+                }
+            }
+        }
+        for i in 0..len {
+            offsets[i + 1] += offsets[i];
+        }
+
+        let mut fill = offsets.clone();
+        let mut preds = vec![0; offsets[len]];
+        for i in 0..len {
+            for edge in self.successors(i) {
+                if let Edge::To(target) = edge {
+                    preds[fill[target]] = i;
+                    fill[target] += 1;
+                }
+            }
+        }
+
+        (offsets, preds)
+    }
+
+    // terminates[i] is true if execution starting at i reaches the end of the program
+    pub fn terminates(&self) -> Vec<bool> {
+        let (offsets, preds) = self.predecessors();
+        let mut terminates = vec![false; self.edges.len()];
+
+        let mut to_visit: Vec<usize> = (0..self.edges.len())
+            .filter(|i| self.successors(*i).any(|e| e == Edge::Halt))
+            .collect();
+        for i in &to_visit {
+            terminates[*i] = true;
+        }
+
+        while let Some(i) = to_visit.pop() {
+            for pred in &preds[offsets[i]..offsets[i + 1]] {
+                if !terminates[*pred] {
+                    terminates[*pred] = true;
+                    to_visit.push(*pred);
+                }
+            }
+        }
+
+        terminates
+    }
+
+    // instructions executed from the start, in order, until the program stops or loops
+    pub fn execution_path(&self) -> Vec<usize> {
+        let mut cpu = Cpu::new(self.instructions.to_vec());
+        let mut path = Vec::new();
+
+        while cpu.get_state() == &CpuState::Ready {
+            let ins_ptr = cpu.get_ins_ptr();
+            if cpu.step() == &CpuState::Ready {
+                path.push(ins_ptr);
+            }
+        }
+
+        path
+    }
+
+    // every single jmp/nop flip on the executed path that makes the program terminate
+    pub fn candidate_repairs(&self) -> Vec<Repair> {
+        let terminates = self.terminates();
+        if self.instructions.is_empty() || terminates[0] {
+            return Vec::new();
+        }
+
+        let len = self.instructions.len();
+        self.execution_path()
+            .into_iter()
+            .filter_map(|index| {
+                let instruction = self.instructions[index];
+                let to = flip(instruction.op)?;
+                let fixed = match edges(index, to, instruction.val, len).0 {
+                    Edge::Halt => true,
+                    Edge::To(next) => terminates[next],
+                    Edge::OutOfBounds(_) => false,
+                };
+                if fixed {
+                    Some(Repair {
+                        index,
+                        from: instruction.op,
+                        to,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn repair(&self) -> Option<Repair> {
+        self.candidate_repairs().into_iter().next()
+    }
+}
+
+pub fn repair(instructions: &[Instruction]) -> Option<Repair> {
+    ControlFlowGraph::new(instructions).repair()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{parse_input, SAMPLE_1};
+    use super::*;
+
+    #[test]
+    fn repair_sample() {
+        let mut instructions = parse_input(SAMPLE_1);
+        let cfg = ControlFlowGraph::new(&instructions);
+        assert_eq!(vec![0, 1, 2, 6, 7, 3, 4], cfg.execution_path());
+
+        let repair = cfg.repair().unwrap();
+        assert_eq!(
+            Repair {
+                index: 7,
+                from: OpCode::Jmp,
+                to: OpCode::Nop
+            },
+            repair
+        );
+        assert_eq!(1, cfg.candidate_repairs().len());
+
+        repair.apply(&mut instructions);
+        let mut cpu = Cpu::new(instructions);
+        assert_eq!(&CpuState::Halted { exit_ptr: 9 }, cpu.run());
+        assert_eq!(8, cpu.get_acc());
+    }
+
+    #[test]
+    fn repair_large() {
+        let len = 300_000;
+        let mut instructions: Vec<Instruction> = (0..len)
+            .map(|i| Instruction {
+                op: if i % 2 == 0 { OpCode::Jmp } else { OpCode::Acc },
+                val: 1,
+            })
+            .collect();
+        instructions[len - 1] = Instruction {
+            op: OpCode::Jmp,
+            val: 1 - len as i64,
+        };
+
+        assert_eq!(
+            Some(Repair {
+                index: len - 1,
+                from: OpCode::Jmp,
+                to: OpCode::Nop
+            }),
+            repair(&instructions)
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use super::scheduler::ChannelTable;
+use super::{Instruction, OpCode};
+
+// Assembler and disassembler for handheld boot code.
+//
+// Source format, one instruction per line:
+//
+//   # comments start with '#' or ';'
+//   start: nop +0
+//   loop:  acc +1
+//          jmp loop
+//   end:
+//
+// Jump operands may be a signed offset or a label, labels resolve to the relative offset
+// the Cpu expects. A label on a line without an instruction marks the next instruction
+// (or the end of the program). snd/rcv operands may name a channel, names are numbered
+// through a ChannelTable so programs assembled against the same table can talk.
+
+#[derive(Debug, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl AsmError {
+    fn new(line: usize, message: String) -> Self {
+        AsmError { line, message }
+    }
+}
+
+impl Display for AsmError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AsmError {}
+
+// nop keeps its offset so a flipped nop still lands on the label
+fn takes_offset(op: OpCode) -> bool {
+    op.is_jump() || op == OpCode::Nop
+}
+
+fn is_label(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+struct Statement<'a> {
+    line: usize,
+    op: OpCode,
+    operand: &'a str,
+}
+
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, AsmError> {
+    assemble_with_channels(source, &mut ChannelTable::default())
+}
+
+pub fn assemble_with_channels(
+    source: &str,
+    channels: &mut ChannelTable,
+) -> Result<Vec<Instruction>, AsmError> {
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut statements: Vec<Statement> = Vec::new();
+
+    // first pass: strip comments, record label positions
+    for (i, raw) in source.lines().enumerate() {
+        let line = i + 1;
+        let mut text = raw.split(&['#', ';'][..]).next().unwrap_or("").trim();
+
+        while let Some(colon) = text.find(':') {
+            let label = text[..colon].trim();
+            if !is_label(label) {
+                return Err(AsmError::new(line, format!("invalid label [{}]", label)));
+            }
+            if labels.insert(label, statements.len()).is_some() {
+                return Err(AsmError::new(line, format!("duplicate label [{}]", label)));
+            }
+            text = text[colon + 1..].trim();
+        }
+
+        if text.is_empty() {
+            continue;
+        }
+
+        let mut parts = text.split_whitespace();
+        let mnemonic = parts.next().unwrap_or("");
+        let op = mnemonic
+            .parse::<OpCode>()
+            .map_err(|_| AsmError::new(line, format!("unknown opcode [{}]", mnemonic)))?;
+        let operand = parts
+            .next()
+            .ok_or_else(|| AsmError::new(line, format!("missing operand for {}", op)))?;
+        if let Some(extra) = parts.next() {
+            return Err(AsmError::new(line, format!("unexpected [{}]", extra)));
+        }
+
+        statements.push(Statement { line, op, operand });
+    }
+
+    // second pass: resolve operands
+    statements
+        .iter()
+        .enumerate()
+        .map(|(index, statement)| {
+            let val = if let Ok(val) = statement.operand.parse::<i64>() {
+                val
+            } else if statement.op.is_channel() && is_label(statement.operand) {
+                channels.id(statement.operand)
+            } else if takes_offset(statement.op) && is_label(statement.operand) {
+                let target = labels.get(statement.operand).ok_or_else(|| {
+                    AsmError::new(
+                        statement.line,
+                        format!("undefined label [{}]", statement.operand),
+                    )
+                })?;
+                *target as i64 - index as i64
+            } else {
+                return Err(AsmError::new(
+                    statement.line,
+                    format!("invalid operand [{}]", statement.operand),
+                ));
+            };
+            Ok(Instruction {
+                op: statement.op,
+                val,
+            })
+        })
+        .collect()
+}
+
+// Produces a listing that assembles back to the same instructions. Every in-range jump
+// target gets a label, out of range targets are kept as raw offsets.
+pub fn disassemble(instructions: &[Instruction]) -> String {
+    let target = |index: usize, instruction: &Instruction| -> Option<usize> {
+        if !instruction.op.is_jump() {
+            return None;
+        }
+        let target = index as i64 + instruction.val;
+        if target >= 0 && target as usize <= instructions.len() {
+            Some(target as usize)
+        } else {
+            None
+        }
+    };
+
+    let targets: BTreeSet<usize> = instructions
+        .iter()
+        .enumerate()
+        .filter_map(|(i, ins)| target(i, ins))
+        .collect();
+
+    let label = |index: usize| format!("L{}", index);
+
+    let mut listing = String::new();
+    for (i, instruction) in instructions.iter().enumerate() {
+        let prefix = if targets.contains(&i) {
+            format!("{}:", label(i))
+        } else {
+            String::new()
+        };
+
+        let line = match target(i, instruction) {
+            Some(t) => format!("{:<8}{} {}", prefix, instruction.op, label(t)),
+            None => format!("{:<8}{}", prefix, instruction),
+        };
+        listing.push_str(line.trim_end());
+        listing.push('\n');
+    }
+
+    if targets.contains(&instructions.len()) {
+        listing.push_str(&format!("{}:\n", label(instructions.len())));
+    }
+
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{parse_input, SAMPLE_1};
+    use super::*;
+
+    #[test]
+    fn assemble_labels() {
+        let source = "# count to three
+start:  nop +0
+loop:   acc +1      ; bump
+        jmp done
+
+        jmp loop
+done:";
+        let instructions = assemble(source).unwrap();
+        assert_eq!(parse_input("nop +0\nacc +1\njmp +2\njmp -2"), instructions);
+    }
+
+    #[test]
+    fn assemble_errors() {
+        assert_eq!(3, assemble("nop +0\n\njmp nowhere").unwrap_err().line);
+        assert_eq!(1, assemble("acc start\nstart: nop +0").unwrap_err().line);
+        assert_eq!(2, assemble("a: nop +0\na: nop +0").unwrap_err().line);
+        assert_eq!(1, assemble("hcf +2").unwrap_err().line);
+    }
+
+    #[test]
+    fn round_trip() {
+        let instructions = parse_input(SAMPLE_1);
+        for (instruction, line) in instructions.iter().zip(SAMPLE_1.lines()) {
+            assert_eq!(line, instruction.to_string());
+        }
+
+        let listing = disassemble(&instructions);
+        assert!(listing.contains("L6:     acc +1\n"));
+        assert!(listing.contains("jmp L1\n"));
+        assert_eq!(instructions, assemble(&listing).unwrap());
+    }
+
+    #[test]
+    fn conditional_labels() {
+        let source = "acc +3
+loop:   out +0
+        sub +1
+        jnz loop
+        jz end
+end:";
+        let instructions = assemble(source).unwrap();
+        assert_eq!(-2, instructions[3].val);
+        assert_eq!(1, instructions[4].val);
+        assert_eq!(instructions, assemble(&disassemble(&instructions)).unwrap());
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::{Cpu, CpuState, Instruction, OpCode};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Breakpoint {
+    Index(usize),
+    Op(OpCode),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Watch {
+    AccEquals(i64),
+    AccAbove(i64),
+    AccBelow(i64),
+    AccChanged,
+}
+
+impl Watch {
+    fn triggered(&self, before: i64, after: i64) -> bool {
+        match self {
+            Watch::AccEquals(n) => after == *n,
+            Watch::AccAbove(n) => after > *n,
+            Watch::AccBelow(n) => after < *n,
+            Watch::AccChanged => after != before,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TraceEntry {
+    pub ins_ptr: usize,
+    pub instruction: Instruction,
+    pub acc_before: i64,
+    pub acc_after: i64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StopReason {
+    Breakpoint(Breakpoint),
+    Watch(Watch),
+    Stopped,
+}
+
+pub struct Debugger {
+    cpu: Cpu,
+    breakpoints: HashSet<Breakpoint>,
+    watches: Vec<Watch>,
+    trace: VecDeque<TraceEntry>,
+    trace_capacity: usize,
+}
+
+impl Debugger {
+    pub fn new(cpu: Cpu) -> Self {
+        Debugger {
+            cpu,
+            breakpoints: HashSet::new(),
+            watches: Vec::new(),
+            trace: VecDeque::new(),
+            trace_capacity: 0,
+        }
+    }
+
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    pub fn into_cpu(self) -> Cpu {
+        self.cpu
+    }
+
+    pub fn set_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.insert(breakpoint);
+    }
+
+    pub fn clear_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        self.breakpoints.remove(breakpoint)
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    pub fn add_watch(&mut self, watch: Watch) {
+        self.watches.push(watch);
+    }
+
+    pub fn clear_watches(&mut self) {
+        self.watches.clear();
+    }
+
+    // a capacity of 0 disables tracing, otherwise only the most recent steps are kept
+    pub fn set_trace_capacity(&mut self, capacity: usize) {
+        self.trace_capacity = capacity;
+        while self.trace.len() > capacity {
+            self.trace.pop_front();
+        }
+    }
+
+    pub fn trace(&self) -> impl Iterator<Item = &TraceEntry> {
+        self.trace.iter()
+    }
+
+    pub fn clear_trace(&mut self) {
+        self.trace.clear();
+    }
+
+    // executes a single instruction, returning the trace entry if one was actually executed
+    pub fn step(&mut self) -> Option<TraceEntry> {
+        let ins_ptr = self.cpu.get_ins_ptr();
+        let instruction = self.cpu.get_instruction().copied();
+        let acc_before = self.cpu.get_acc();
+
+        if self.cpu.step() != &CpuState::Ready {
+            return None;
+        }
+
+        let entry = TraceEntry {
+            ins_ptr,
+            instruction: instruction?,
+            acc_before,
+            acc_after: self.cpu.get_acc(),
+        };
+
+        if self.trace_capacity > 0 {
+            if self.trace.len() == self.trace_capacity {
+                self.trace.pop_front();
+            }
+            self.trace.push_back(entry);
+        }
+
+        Some(entry)
+    }
+
+    // always executes at least one instruction, so continuing from a breakpoint makes progress
+    pub fn run_until_break(&mut self) -> StopReason {
+        loop {
+            let entry = match self.step() {
+                Some(entry) => entry,
+                None => return StopReason::Stopped,
+            };
+
+            if let Some(watch) = self
+                .watches
+                .iter()
+                .find(|w| w.triggered(entry.acc_before, entry.acc_after))
+            {
+                return StopReason::Watch(*watch);
+            }
+
+            if let Some(breakpoint) = self.breakpoint_at_ins_ptr() {
+                return StopReason::Breakpoint(breakpoint);
+            }
+        }
+    }
+
+    fn breakpoint_at_ins_ptr(&self) -> Option<Breakpoint> {
+        let index = Breakpoint::Index(self.cpu.get_ins_ptr());
+        if self.breakpoints.contains(&index) {
+            return Some(index);
+        }
+
+        let op = Breakpoint::Op(self.cpu.get_instruction()?.op);
+        if self.breakpoints.contains(&op) {
+            return Some(op);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{parse_input, SAMPLE_1};
+    use super::*;
+
+    #[test]
+    fn break_on_index_and_op() {
+        let mut debugger = Debugger::new(Cpu::new(parse_input(SAMPLE_1)));
+        debugger.set_breakpoint(Breakpoint::Index(4));
+        debugger.set_breakpoint(Breakpoint::Op(OpCode::Jmp));
+
+        assert_eq!(
+            StopReason::Breakpoint(Breakpoint::Op(OpCode::Jmp)),
+            debugger.run_until_break()
+        );
+        assert_eq!(2, debugger.cpu().get_ins_ptr());
+
+        debugger.clear_breakpoint(&Breakpoint::Op(OpCode::Jmp));
+        assert_eq!(
+            StopReason::Breakpoint(Breakpoint::Index(4)),
+            debugger.run_until_break()
+        );
+        assert_eq!(StopReason::Stopped, debugger.run_until_break());
+        assert_eq!(
+            &CpuState::InfiniteLoop {
+                entry: 1,
+                body: vec![1, 2, 6, 7, 3, 4]
+            },
+            debugger.cpu().get_state()
+        );
+        assert_eq!(5, debugger.cpu().get_acc());
+    }
+
+    #[test]
+    fn watch_and_trace() {
+        let mut debugger = Debugger::new(Cpu::new(parse_input(SAMPLE_1)));
+        debugger.set_trace_capacity(3);
+        debugger.add_watch(Watch::AccAbove(3));
+
+        assert_eq!(
+            StopReason::Watch(Watch::AccAbove(3)),
+            debugger.run_until_break()
+        );
+        assert_eq!(5, debugger.cpu().get_acc());
+
+        let trace: Vec<&TraceEntry> = debugger.trace().collect();
+        assert_eq!(3, trace.len());
+        assert_eq!(7, trace[1].ins_ptr);
+        assert_eq!(
+            TraceEntry {
+                ins_ptr: 3,
+                instruction: Instruction {
+                    op: OpCode::Acc,
+                    val: 3
+                },
+                acc_before: 2,
+                acc_after: 5,
+            },
+            *trace[2]
+        );
+    }
+}
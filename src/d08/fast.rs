@@ -0,0 +1,515 @@
+use std::convert::TryFrom;
+
+use super::{CpuState, Instruction, Memory, OpCode, REGISTERS};
+
+// High throughput interpreter for long running programs.
+//
+// The program is decoded once into basic blocks: straight line code that is only ever
+// entered at its first instruction and left through its last one. Jump targets are resolved
+// to block indices up front, runs of `acc` are fused into a single add, and since a block is
+// always entered at its start, loop detection only needs one bit per block.
+//
+// FastCpu reaches the same terminal state as Cpu for every program, it just can't be single
+// stepped, snapshotted or debugged.
+
+#[derive(Debug, Copy, Clone)]
+enum Target {
+    Block(usize),
+    // at or past the end of the program
+    Exit(usize),
+    OutOfBounds(i64),
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Op {
+    // a fused run of acc instructions, the prefix bounds decide up front if any of them
+    // would overflow
+    AddAcc {
+        total: i64,
+        min_prefix: i128,
+        max_prefix: i128,
+        count: usize,
+    },
+    Sub(i64),
+    Mul(i64),
+    Mod(i64),
+    Ld(Option<usize>),
+    St(Option<usize>),
+    Swp(Option<usize>),
+    Out,
+    Snd(i64),
+    Nop,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Exit {
+    Next(Target),
+    Jmp(Target),
+    Jz(Target, Target),
+    Jnz(Target, Target),
+    Rcv(i64),
+}
+
+#[derive(Debug)]
+struct Block {
+    start: usize,
+    end: usize,
+    // (ins_ptr, op) for everything before the exit instruction
+    ops: Vec<(usize, Op)>,
+    // ins_ptr of the exit instruction, the exit itself for fall through blocks
+    exit_ptr: usize,
+    exit: Exit,
+}
+
+fn is_block_end(op: OpCode) -> bool {
+    op.is_jump() || op == OpCode::Rcv
+}
+
+fn decode(instructions: &[Instruction]) -> (Vec<Block>, Vec<usize>) {
+    let len = instructions.len();
+
+    let mut leader = vec![false; len + 1];
+    leader[0] = true;
+    for (i, ins) in instructions.iter().enumerate() {
+        if is_block_end(ins.op) {
+            leader[i + 1] = true;
+        }
+        if ins.op.is_jump() {
+            let target = i as i64 + ins.val;
+            if target >= 0 && (target as usize) < len {
+                leader[target as usize] = true;
+            }
+        }
+    }
+
+    // block index of every leader, usize::MAX elsewhere
+    let mut block_of = vec![usize::MAX; len];
+    let mut count = 0;
+    for i in 0..len {
+        if leader[i] {
+            block_of[i] = count;
+            count += 1;
+        }
+    }
+
+    let target = |target: i64| -> Target {
+        if target < 0 {
+            Target::OutOfBounds(target)
+        } else if target as usize >= len {
+            Target::Exit(target as usize)
+        } else {
+            Target::Block(block_of[target as usize])
+        }
+    };
+
+    let mut blocks = Vec::with_capacity(count);
+    let mut start = 0;
+    while start < len {
+        let mut ops: Vec<(usize, Op)> = Vec::new();
+        let mut i = start;
+        let exit = loop {
+            if i == len || (i > start && leader[i]) {
+                break Exit::Next(target(i as i64));
+            }
+
+            let ins = instructions[i];
+            let jump = target(i as i64 + ins.val);
+            let next = target(i as i64 + 1);
+            let op = match ins.op {
+                OpCode::Jmp => break Exit::Jmp(jump),
+                OpCode::Jz => break Exit::Jz(jump, next),
+                OpCode::Jnz => break Exit::Jnz(jump, next),
+                OpCode::Rcv => break Exit::Rcv(ins.val),
+                OpCode::Acc => {
+                    let prefix = ins.val as i128;
+                    if let Some((
+                        _,
+                        Op::AddAcc {
+                            total,
+                            min_prefix,
+                            max_prefix,
+                            count,
+                        },
+                    )) = ops.last_mut()
+                    {
+                        let prefix = *total as i128 + prefix;
+                        if let Ok(sum) = i64::try_from(prefix) {
+                            *total = sum;
+                            *min_prefix = (*min_prefix).min(prefix);
+                            *max_prefix = (*max_prefix).max(prefix);
+                            *count += 1;
+                            i += 1;
+                            continue;
+                        }
+                    }
+                    Op::AddAcc {
+                        total: ins.val,
+                        min_prefix: prefix,
+                        max_prefix: prefix,
+                        count: 1,
+                    }
+                }
+                OpCode::Sub => Op::Sub(ins.val),
+                OpCode::Mul => Op::Mul(ins.val),
+                OpCode::Mod => Op::Mod(ins.val),
+                OpCode::Ld => Op::Ld(Memory::address(ins.val)),
+                OpCode::St => Op::St(Memory::address(ins.val)),
+                OpCode::Swp => Op::Swp(usize::try_from(ins.val).ok().filter(|r| *r < REGISTERS)),
+                OpCode::Out => Op::Out,
+                OpCode::Snd => Op::Snd(ins.val),
+                OpCode::Nop => Op::Nop,
+            };
+            ops.push((i, op));
+            i += 1;
+        };
+
+        let end = match exit {
+            Exit::Next(_) => i,
+            _ => i + 1,
+        };
+        blocks.push(Block {
+            start,
+            end,
+            ops,
+            exit_ptr: i,
+            exit,
+        });
+        start = end;
+    }
+
+    (blocks, block_of)
+}
+
+pub struct FastCpu {
+    state: CpuState,
+    instructions: Vec<Instruction>,
+    blocks: Vec<Block>,
+    block_of: Vec<usize>,
+    // the block about to run while Ready
+    block: usize,
+    ins_ptr: usize,
+    acc_register: i64,
+    registers: [i64; REGISTERS],
+    memory: Memory,
+    output: Vec<i64>,
+    sent: Vec<(i64, i64)>,
+    detect_loops: bool,
+    visited_blocks: Vec<u64>,
+    path: Vec<usize>,
+}
+
+enum Flow {
+    Continue,
+    Fault(usize),
+}
+
+impl FastCpu {
+    pub fn new(instructions: Vec<Instruction>) -> Self {
+        let (blocks, block_of) = decode(&instructions);
+        let state = if instructions.is_empty() {
+            CpuState::Halted { exit_ptr: 0 }
+        } else {
+            CpuState::Ready
+        };
+
+        FastCpu {
+            state,
+            visited_blocks: vec![0; blocks.len().div_ceil(64)],
+            instructions,
+            blocks,
+            block_of,
+            block: 0,
+            ins_ptr: 0,
+            acc_register: 0,
+            registers: [0; REGISTERS],
+            memory: Memory::default(),
+            output: Vec::new(),
+            sent: Vec::new(),
+            detect_loops: true,
+            path: Vec::new(),
+        }
+    }
+
+    pub fn set_loop_detection(&mut self, enabled: bool) {
+        self.detect_loops = enabled;
+    }
+
+    pub fn get_acc(&self) -> i64 {
+        self.acc_register
+    }
+
+    pub fn get_register(&self, register: usize) -> Option<i64> {
+        self.registers.get(register).copied()
+    }
+
+    pub fn get_memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn get_output(&self) -> &[i64] {
+        &self.output
+    }
+
+    pub fn take_sent(&mut self) -> Vec<(i64, i64)> {
+        std::mem::take(&mut self.sent)
+    }
+
+    pub fn get_ins_ptr(&self) -> usize {
+        self.ins_ptr
+    }
+
+    pub fn get_state(&self) -> &CpuState {
+        &self.state
+    }
+
+    pub fn deliver(&mut self, value: i64) -> bool {
+        if !matches!(self.state, CpuState::Blocked { .. }) {
+            return false;
+        }
+        // the instruction after a rcv always starts a block
+        let next = self.ins_ptr + 1;
+        let target = if next < self.instructions.len() {
+            Target::Block(self.block_of[next])
+        } else {
+            Target::Exit(next)
+        };
+        self.acc_register = value;
+        self.jump(target, self.ins_ptr);
+        true
+    }
+
+    // moves to the target block, or stops if the target leaves the program
+    fn jump(&mut self, target: Target, from: usize) {
+        match target {
+            Target::Block(block) => {
+                self.block = block;
+                self.ins_ptr = self.blocks[block].start;
+                self.state = CpuState::Ready;
+            }
+            Target::Exit(exit_ptr) => {
+                self.ins_ptr = exit_ptr;
+                self.state = CpuState::Halted { exit_ptr };
+            }
+            Target::OutOfBounds(target) => {
+                self.ins_ptr = from;
+                self.state = CpuState::JumpOutOfBounds { from, target };
+            }
+        }
+    }
+
+    fn run_ops(&mut self, block: usize) -> Flow {
+        for &(ins_ptr, op) in &self.blocks[block].ops {
+            let acc = self.acc_register;
+            self.acc_register = match op {
+                Op::AddAcc {
+                    total,
+                    min_prefix,
+                    max_prefix,
+                    count,
+                } => {
+                    let base = acc as i128;
+                    if base + max_prefix > i64::MAX as i128 || base + min_prefix < i64::MIN as i128
+                    {
+                        // find the instruction that overflows, one add at a time
+                        let mut acc = acc;
+                        for i in ins_ptr..ins_ptr + count {
+                            match acc.checked_add(self.instructions[i].val) {
+                                Some(sum) => acc = sum,
+                                None => {
+                                    self.acc_register = acc;
+                                    return Flow::Fault(i);
+                                }
+                            }
+                        }
+                    }
+                    acc + total
+                }
+                Op::Sub(val) => match acc.checked_sub(val) {
+                    Some(acc) => acc,
+                    None => return Flow::Fault(ins_ptr),
+                },
+                Op::Mul(val) => match acc.checked_mul(val) {
+                    Some(acc) => acc,
+                    None => return Flow::Fault(ins_ptr),
+                },
+                Op::Mod(val) => match acc.checked_rem_euclid(val) {
+                    Some(acc) => acc,
+                    None => return Flow::Fault(ins_ptr),
+                },
+                Op::Ld(Some(address)) => self.memory.read(address),
+                Op::St(Some(address)) => {
+                    self.memory.write(address, acc);
+                    acc
+                }
+                Op::Swp(Some(register)) => std::mem::replace(&mut self.registers[register], acc),
+                Op::Ld(None) | Op::St(None) | Op::Swp(None) => return Flow::Fault(ins_ptr),
+                Op::Out => {
+                    self.output.push(acc);
+                    acc
+                }
+                Op::Snd(channel) => {
+                    self.sent.push((channel, acc));
+                    acc
+                }
+                Op::Nop => acc,
+            };
+        }
+        Flow::Continue
+    }
+
+    fn loop_body(&self, block: usize) -> Vec<usize> {
+        let start = self.path.iter().position(|b| *b == block).unwrap();
+        self.path[start..]
+            .iter()
+            .flat_map(|b| self.blocks[*b].start..self.blocks[*b].end)
+            .collect()
+    }
+
+    pub fn run(&mut self) -> &CpuState {
+        while self.state == CpuState::Ready {
+            let block = self.block;
+
+            if self.detect_loops {
+                let (word, bit) = (block / 64, 1 << (block % 64));
+                if self.visited_blocks[word] & bit != 0 {
+                    self.state = CpuState::InfiniteLoop {
+                        entry: self.blocks[block].start,
+                        body: self.loop_body(block),
+                    };
+                    break;
+                }
+                self.visited_blocks[word] |= bit;
+                self.path.push(block);
+            }
+
+            if let Flow::Fault(at) = self.run_ops(block) {
+                self.ins_ptr = at;
+                self.state = CpuState::Fault {
+                    at,
+                    instruction: self.instructions[at],
+                };
+                break;
+            }
+
+            let exit_ptr = self.blocks[block].exit_ptr;
+            match self.blocks[block].exit {
+                Exit::Next(target) | Exit::Jmp(target) => self.jump(target, exit_ptr),
+                Exit::Jz(jump, _) if self.acc_register == 0 => self.jump(jump, exit_ptr),
+                Exit::Jnz(jump, _) if self.acc_register != 0 => self.jump(jump, exit_ptr),
+                Exit::Jz(_, next) | Exit::Jnz(_, next) => self.jump(next, exit_ptr),
+                Exit::Rcv(channel) => {
+                    self.ins_ptr = exit_ptr;
+                    self.state = CpuState::Blocked { channel };
+                }
+            }
+        }
+        &self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{parse_input, Cpu, SAMPLE_1};
+    use super::*;
+
+    // xorshift, enough to scramble test programs
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+    }
+
+    const OPS: [OpCode; 14] = [
+        OpCode::Acc,
+        OpCode::Acc,
+        OpCode::Jmp,
+        OpCode::Nop,
+        OpCode::Sub,
+        OpCode::Mul,
+        OpCode::Mod,
+        OpCode::Jz,
+        OpCode::Jnz,
+        OpCode::Ld,
+        OpCode::St,
+        OpCode::Swp,
+        OpCode::Out,
+        OpCode::Snd,
+    ];
+
+    fn assert_agree(instructions: Vec<Instruction>, detect_loops: bool) {
+        let mut cpu = Cpu::new(instructions.clone());
+        cpu.set_loop_detection(detect_loops);
+        let mut fast = FastCpu::new(instructions.clone());
+        fast.set_loop_detection(detect_loops);
+
+        assert_eq!(cpu.run(), fast.run(), "{:?}", instructions);
+        assert_eq!(cpu.get_acc(), fast.get_acc());
+        assert_eq!(cpu.get_ins_ptr(), fast.get_ins_ptr());
+        assert_eq!(cpu.get_output(), fast.get_output());
+        assert_eq!(cpu.get_memory(), fast.get_memory());
+        assert_eq!(cpu.take_sent(), fast.take_sent());
+        for r in 0..REGISTERS {
+            assert_eq!(cpu.get_register(r), fast.get_register(r));
+        }
+    }
+
+    #[test]
+    fn agrees_on_sample_and_repairs() {
+        let instructions = parse_input(SAMPLE_1);
+        assert_agree(instructions.clone(), true);
+
+        for i in 0..instructions.len() {
+            let mut flipped = instructions.clone();
+            flipped[i].op = match flipped[i].op {
+                OpCode::Jmp => OpCode::Nop,
+                OpCode::Nop => OpCode::Jmp,
+                _ => continue,
+            };
+            assert_agree(flipped, true);
+        }
+    }
+
+    #[test]
+    fn agrees_on_edge_cases() {
+        let programs = [
+            "",
+            "jmp -1",
+            "nop +0\njmp +7",
+            "acc +9223372036854775807\nacc -1\nacc +1\nacc +1",
+            "acc -9223372036854775807\nacc -1\nacc -1",
+            "acc +5\nmod +0",
+            "acc +5\nrcv +3\nout +0",
+            "ld +70000",
+            "acc +1\njz +2\njnz +2\nacc +100\nout +0",
+        ];
+        for program in &programs {
+            assert_agree(parse_input(program), true);
+        }
+        assert_agree(parse_input("acc +3\nout +0\nsub +1\njnz -2"), false);
+    }
+
+    #[test]
+    fn agrees_on_random_programs() {
+        let mut rng = Rng(0x2020_0808);
+        for _ in 0..2000 {
+            let len = 1 + rng.next(30) as i64;
+            let instructions: Vec<Instruction> = (0..len)
+                .map(|_| {
+                    let op = OPS[rng.next(OPS.len() as u64) as usize];
+                    let val = match op {
+                        OpCode::Swp => rng.next(REGISTERS as u64 + 1) as i64,
+                        OpCode::Ld | OpCode::St => rng.next(4) as i64,
+                        _ => rng.next(2 * len as u64 + 1) as i64 - len,
+                    };
+                    Instruction { op, val }
+                })
+                .collect();
+            assert_agree(instructions, true);
+        }
+    }
+}
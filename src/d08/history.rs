@@ -0,0 +1,238 @@
+use std::collections::{HashSet, VecDeque};
+use std::convert::TryFrom;
+
+use super::{Cpu, CpuState, Memory, OpCode, REGISTERS};
+
+// Snapshots and reverse execution for the Cpu.
+//
+// A snapshot is a full copy of the machine state. Reverse stepping instead keeps a journal
+// with just enough of the old state to undo each step: the instruction pointer and
+// accumulator, plus the single register or memory word the instruction could overwrite.
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Snapshot {
+    state: CpuState,
+    ins_ptr: usize,
+    acc_register: i64,
+    registers: [i64; REGISTERS],
+    memory: Memory,
+    output: Vec<i64>,
+    sent: Vec<(i64, i64)>,
+    visited_instructions: HashSet<usize>,
+    path: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(super) struct UndoEntry {
+    state: CpuState,
+    ins_ptr: usize,
+    acc_register: i64,
+    register: Option<(usize, i64)>,
+    memory: Option<(usize, i64)>,
+    output_len: usize,
+    sent_len: usize,
+    // whether the step added ins_ptr to the visited set and path
+    visited: bool,
+}
+
+#[derive(Debug, PartialEq, Default)]
+pub(super) struct Journal {
+    capacity: usize,
+    entries: VecDeque<UndoEntry>,
+}
+
+impl Journal {
+    pub(super) fn push(&mut self, entry: UndoEntry) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+}
+
+impl Cpu {
+    // a capacity of 0 disables the journal, otherwise only the most recent steps can be undone
+    pub fn set_journal_capacity(&mut self, capacity: usize) {
+        self.journal.capacity = capacity;
+        while self.journal.entries.len() > capacity {
+            self.journal.entries.pop_front();
+        }
+    }
+
+    pub fn journal_len(&self) -> usize {
+        self.journal.entries.len()
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            state: self.state.clone(),
+            ins_ptr: self.ins_ptr,
+            acc_register: self.acc_register,
+            registers: self.registers,
+            memory: self.memory.clone(),
+            output: self.output.clone(),
+            sent: self.sent.clone(),
+            visited_instructions: self.visited_instructions.clone(),
+            path: self.path.clone(),
+        }
+    }
+
+    // the journal describes how we got to the current state, so it is discarded on restore
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.state = snapshot.state.clone();
+        self.ins_ptr = snapshot.ins_ptr;
+        self.acc_register = snapshot.acc_register;
+        self.registers = snapshot.registers;
+        self.memory = snapshot.memory.clone();
+        self.output = snapshot.output.clone();
+        self.sent = snapshot.sent.clone();
+        self.visited_instructions = snapshot.visited_instructions.clone();
+        self.path = snapshot.path.clone();
+        self.journal.entries.clear();
+    }
+
+    // undoes up to `steps` steps, returning how many were undone. Values already taken
+    // from the Cpu with take_sent can't be recalled.
+    pub fn step_back(&mut self, steps: usize) -> usize {
+        for undone in 0..steps {
+            let entry = match self.journal.entries.pop_back() {
+                Some(entry) => entry,
+                None => return undone,
+            };
+
+            if entry.visited {
+                self.visited_instructions.remove(&entry.ins_ptr);
+                self.path.pop();
+            }
+            if let Some((register, value)) = entry.register {
+                self.registers[register] = value;
+            }
+            if let Some((address, value)) = entry.memory {
+                self.memory.write(address, value);
+            }
+            self.output.truncate(entry.output_len);
+            self.sent.truncate(entry.sent_len.min(self.sent.len()));
+            self.state = entry.state;
+            self.ins_ptr = entry.ins_ptr;
+            self.acc_register = entry.acc_register;
+        }
+        steps
+    }
+
+    // captures what the next step may overwrite, only steps from a ready Cpu (or a delivery
+    // to a blocked one) change anything
+    pub(super) fn undo_entry(&self) -> Option<UndoEntry> {
+        let active = matches!(self.state, CpuState::Ready | CpuState::Blocked { .. });
+        if self.journal.capacity == 0 || !active {
+            return None;
+        }
+
+        let mut entry = UndoEntry {
+            state: self.state.clone(),
+            ins_ptr: self.ins_ptr,
+            acc_register: self.acc_register,
+            register: None,
+            memory: None,
+            output_len: self.output.len(),
+            sent_len: self.sent.len(),
+            visited: self.detect_loops
+                && self.ins_ptr < self.instructions.len()
+                && !self.visited_instructions.contains(&self.ins_ptr),
+        };
+
+        if let Some(instruction) = self.get_instruction() {
+            match instruction.op {
+                OpCode::Swp => {
+                    entry.register = usize::try_from(instruction.val)
+                        .ok()
+                        .filter(|r| *r < REGISTERS)
+                        .map(|r| (r, self.registers[r]));
+                }
+                OpCode::St => {
+                    entry.memory = Memory::address(instruction.val)
+                        .map(|address| (address, self.memory.read(address)));
+                }
+                _ => {}
+            }
+        }
+
+        Some(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{parse_input, Cpu, CpuState, SAMPLE_1};
+
+    #[test]
+    fn step_back_to_snapshot() {
+        let mut cpu = Cpu::new(parse_input(SAMPLE_1));
+        cpu.set_journal_capacity(100);
+        let start = cpu.snapshot();
+
+        for _ in 0..3 {
+            cpu.step();
+        }
+        let middle = cpu.snapshot();
+
+        assert!(matches!(cpu.run(), CpuState::InfiniteLoop { .. }));
+        assert_eq!(8, cpu.journal_len());
+
+        assert_eq!(5, cpu.step_back(5));
+        assert_eq!(middle, cpu.snapshot());
+
+        assert_eq!(3, cpu.step_back(10));
+        assert_eq!(start, cpu.snapshot());
+
+        // replaying from the start gives the same result
+        assert!(matches!(cpu.run(), CpuState::InfiniteLoop { .. }));
+        assert_eq!(5, cpu.get_acc());
+
+        cpu.restore(&middle);
+        assert_eq!(0, cpu.journal_len());
+        assert!(matches!(cpu.run(), CpuState::InfiniteLoop { .. }));
+        assert_eq!(5, cpu.get_acc());
+    }
+
+    #[test]
+    fn step_back_memory_and_registers() {
+        let mut cpu = Cpu::new(parse_input(SAMPLE_2));
+        cpu.set_journal_capacity(2);
+        let mut snapshots = vec![cpu.snapshot()];
+        while cpu.step() == &CpuState::Ready {
+            snapshots.push(cpu.snapshot());
+        }
+        assert!(cpu.is_clean_exit());
+
+        // only the last two steps were kept
+        assert_eq!(2, cpu.step_back(3));
+        assert_eq!(snapshots[snapshots.len() - 2], cpu.snapshot());
+    }
+
+    #[test]
+    fn step_back_each_step() {
+        let mut cpu = Cpu::new(parse_input(SAMPLE_2));
+        cpu.set_journal_capacity(100);
+        let mut snapshots = vec![cpu.snapshot()];
+        while cpu.step() == &CpuState::Ready {
+            snapshots.push(cpu.snapshot());
+        }
+
+        // halting is a step of its own
+        assert_eq!(snapshots.len(), cpu.journal_len());
+        cpu.step_back(1);
+        while let Some(snapshot) = snapshots.pop() {
+            assert_eq!(snapshot, cpu.snapshot());
+            cpu.step_back(1);
+        }
+    }
+
+    const SAMPLE_2: &str = "acc +7
+st +3
+swp +2
+out +0
+st +3
+acc +1
+swp +2
+out +0";
+}
@@ -0,0 +1,318 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::{Cpu, CpuState};
+
+// Runs several Cpus side by side, connected by channels.
+//
+// `snd c` queues the accumulator on channel c, `rcv c` blocks until a value is available
+// and loads it into the accumulator. Channels are shared queues: any Cpu may send to or
+// receive from any channel, values are received in the order they were sent.
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChannelTable {
+    names: Vec<String>,
+}
+
+impl ChannelTable {
+    // numbers a channel name, reusing the number if the name was seen before
+    pub fn id(&mut self, name: &str) -> i64 {
+        match self.names.iter().position(|n| n == name) {
+            Some(id) => id as i64,
+            None => {
+                self.names.push(name.to_string());
+                (self.names.len() - 1) as i64
+            }
+        }
+    }
+
+    pub fn name(&self, id: i64) -> Option<&str> {
+        if id < 0 {
+            return None;
+        }
+        self.names.get(id as usize).map(|n| n.as_str())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Policy {
+    // every runnable Cpu executes one instruction per round
+    RoundRobin,
+    // only the runnable Cpus with the highest priority run, taking turns
+    Priority,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Outcome {
+    Finished,
+    Deadlock,
+    StepLimit,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CpuReport {
+    pub name: String,
+    pub state: CpuState,
+    pub acc: i64,
+    pub ins_ptr: usize,
+    pub steps: usize,
+    pub output: Vec<i64>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Report {
+    pub outcome: Outcome,
+    pub cpus: Vec<CpuReport>,
+    // values sent but never received, keyed by channel name (or number if unnamed)
+    pub undelivered: Vec<(String, Vec<i64>)>,
+}
+
+struct Process {
+    name: String,
+    cpu: Cpu,
+    priority: u32,
+    steps: usize,
+}
+
+pub struct Scheduler {
+    policy: Policy,
+    processes: Vec<Process>,
+    channels: HashMap<i64, VecDeque<i64>>,
+    table: ChannelTable,
+    next: usize,
+}
+
+impl Scheduler {
+    pub fn new(policy: Policy, table: ChannelTable) -> Self {
+        Scheduler {
+            policy,
+            processes: Vec::new(),
+            channels: HashMap::new(),
+            table,
+            next: 0,
+        }
+    }
+
+    // higher priority runs first under Policy::Priority, ignored for round robin
+    pub fn add(&mut self, name: &str, cpu: Cpu, priority: u32) -> usize {
+        self.processes.push(Process {
+            name: name.to_string(),
+            cpu,
+            priority,
+            steps: 0,
+        });
+        self.processes.len() - 1
+    }
+
+    pub fn cpu(&self, index: usize) -> &Cpu {
+        &self.processes[index].cpu
+    }
+
+    // queues a value on a channel from outside the Cpus
+    pub fn send(&mut self, channel: i64, value: i64) {
+        self.channels.entry(channel).or_default().push_back(value);
+    }
+
+    fn deliver(&mut self) {
+        for process in &mut self.processes {
+            if let CpuState::Blocked { channel } = process.cpu.get_state() {
+                if let Some(value) = self.channels.get_mut(channel).and_then(|q| q.pop_front()) {
+                    process.cpu.deliver(value);
+                }
+            }
+        }
+    }
+
+    fn runnable(&self) -> Vec<usize> {
+        let ready: Vec<usize> = (0..self.processes.len())
+            .filter(|i| self.processes[*i].cpu.get_state() == &CpuState::Ready)
+            .collect();
+
+        match self.policy {
+            Policy::RoundRobin => ready,
+            Policy::Priority => {
+                let top = ready.iter().map(|i| self.processes[*i].priority).max();
+                let mut ready: Vec<usize> = ready
+                    .into_iter()
+                    .filter(|i| Some(self.processes[*i].priority) == top)
+                    .collect();
+                // take turns within the priority level, starting after the last one to run
+                let already_run = ready.iter().filter(|i| **i < self.next).count();
+                ready.rotate_left(already_run);
+                ready.truncate(1);
+                ready
+            }
+        }
+    }
+
+    fn run_process(&mut self, index: usize) {
+        let process = &mut self.processes[index];
+        process.cpu.step();
+        process.steps += 1;
+        for (channel, value) in process.cpu.take_sent() {
+            self.channels.entry(channel).or_default().push_back(value);
+        }
+        self.next = index + 1;
+    }
+
+    // runs until every Cpu has stopped, all remaining ones are blocked, or max_steps
+    // instructions have been executed in total
+    pub fn run(&mut self, max_steps: usize) -> Report {
+        let mut steps = 0;
+        let outcome = loop {
+            self.deliver();
+
+            let runnable = self.runnable();
+            if runnable.is_empty() {
+                let blocked = self
+                    .processes
+                    .iter()
+                    .any(|p| matches!(p.cpu.get_state(), CpuState::Blocked { .. }));
+                break if blocked {
+                    Outcome::Deadlock
+                } else {
+                    Outcome::Finished
+                };
+            }
+
+            for index in runnable {
+                if steps == max_steps {
+                    break;
+                }
+                self.run_process(index);
+                steps += 1;
+            }
+
+            if steps == max_steps {
+                break Outcome::StepLimit;
+            }
+        };
+
+        self.report(outcome)
+    }
+
+    fn report(&self, outcome: Outcome) -> Report {
+        let cpus = self
+            .processes
+            .iter()
+            .map(|p| CpuReport {
+                name: p.name.clone(),
+                state: p.cpu.get_state().clone(),
+                acc: p.cpu.get_acc(),
+                ins_ptr: p.cpu.get_ins_ptr(),
+                steps: p.steps,
+                output: p.cpu.get_output().to_vec(),
+            })
+            .collect();
+
+        let mut undelivered: Vec<(i64, Vec<i64>)> = self
+            .channels
+            .iter()
+            .filter(|(_c, q)| !q.is_empty())
+            .map(|(c, q)| (*c, q.iter().copied().collect()))
+            .collect();
+        undelivered.sort();
+
+        Report {
+            outcome,
+            cpus,
+            undelivered: undelivered
+                .into_iter()
+                .map(|(c, values)| {
+                    let name = self
+                        .table
+                        .name(c)
+                        .map(|n| n.to_string())
+                        .unwrap_or_else(|| c.to_string());
+                    (name, values)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::asm::assemble_with_channels;
+    use super::super::parse_input;
+    use super::*;
+
+    fn scheduler(policy: Policy, programs: &[(&str, &str, u32)]) -> Scheduler {
+        let mut table = ChannelTable::default();
+        let cpus: Vec<(&str, Cpu, u32)> = programs
+            .iter()
+            .map(|(name, source, priority)| {
+                let instructions = assemble_with_channels(source, &mut table).unwrap();
+                (*name, Cpu::new(instructions), *priority)
+            })
+            .collect();
+
+        let mut scheduler = Scheduler::new(policy, table);
+        for (name, cpu, priority) in cpus {
+            scheduler.add(name, cpu, priority);
+        }
+        scheduler
+    }
+
+    #[test]
+    fn ping_pong() {
+        let mut scheduler = scheduler(
+            Policy::RoundRobin,
+            &[
+                ("a", "acc +4\nsnd ping\nrcv pong\nout +0", 0),
+                ("b", "rcv ping\nmul +10\nsnd pong", 0),
+            ],
+        );
+        let report = scheduler.run(100);
+
+        assert_eq!(Outcome::Finished, report.outcome);
+        assert_eq!(vec![40], report.cpus[0].output);
+        assert_eq!(CpuState::Halted { exit_ptr: 3 }, report.cpus[1].state);
+        assert!(report.undelivered.is_empty());
+    }
+
+    #[test]
+    fn deadlock() {
+        let mut scheduler = scheduler(
+            Policy::RoundRobin,
+            &[
+                ("a", "snd spare\nrcv left\nsnd right", 0),
+                ("b", "rcv right\nsnd left", 0),
+            ],
+        );
+        let report = scheduler.run(100);
+
+        assert_eq!(Outcome::Deadlock, report.outcome);
+        assert_eq!(CpuState::Blocked { channel: 1 }, report.cpus[0].state);
+        assert_eq!(CpuState::Blocked { channel: 2 }, report.cpus[1].state);
+        assert_eq!(vec![("spare".to_string(), vec![0])], report.undelivered);
+    }
+
+    #[test]
+    fn priority() {
+        let consumer = "rcv data\nout +0\nrcv data\nout +0\nrcv data\nout +0\nrcv data\nout +0";
+        let mut scheduler = scheduler(
+            Policy::Priority,
+            &[
+                ("low", "acc +1\nsnd data\nacc +1\nsnd data", 1),
+                ("high", "acc +10\nsnd data\nacc +10\nsnd data", 2),
+                ("consumer", consumer, 3),
+            ],
+        );
+        let report = scheduler.run(100);
+
+        assert_eq!(Outcome::Finished, report.outcome);
+        assert_eq!(vec![10, 20, 1, 2], report.cpus[2].output);
+    }
+
+    #[test]
+    fn step_limit() {
+        let mut cpu = Cpu::new(parse_input("jmp +0"));
+        cpu.set_loop_detection(false);
+        let mut scheduler = Scheduler::new(Policy::RoundRobin, ChannelTable::default());
+        scheduler.add("spin", cpu, 0);
+
+        let report = scheduler.run(10);
+        assert_eq!(Outcome::StepLimit, report.outcome);
+        assert_eq!(10, report.cpus[0].steps);
+    }
+}
@@ -1,5 +1,7 @@
 use std::error::Error;
 
+use crate::error::{parse_numbers, ParseError};
+
 fn two_sum(entries: &[usize], sum: usize) -> Result<usize, Box<dyn Error>> {
     for i in 0..entries.len() {
         for j in i..entries.len() {
@@ -17,20 +19,24 @@ fn two_sum(entries: &[usize], sum: usize) -> Result<usize, Box<dyn Error>> {
 
 #[aoc_generator(day9)]
 pub fn parse_input(input: &str) -> Vec<usize> {
-    input.lines().map(|l| l.parse::<usize>().unwrap()).collect()
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<Vec<usize>, ParseError> {
+    parse_numbers(9, input)
 }
 
 const PREAMBLE_SIZE: usize = 25;
 
-#[aoc(day9, part1)]
-pub fn part1(entries: &[usize]) -> usize {
+// the first number that isn't the sum of two of the preamble_size numbers before it
+pub fn first_invalid(entries: &[usize], preamble_size: usize) -> usize {
     let mut preamble: Vec<usize> = Vec::new();
 
-    for i in 0..PREAMBLE_SIZE {
+    for i in 0..preamble_size {
         preamble.push(*entries.get(i).unwrap());
     }
 
-    for i in PREAMBLE_SIZE..entries.len() {
+    for i in preamble_size..entries.len() {
         let cur = entries.get(i).unwrap();
 
         if let Ok(_n) = two_sum(&preamble, *cur) {
@@ -45,10 +51,8 @@ pub fn part1(entries: &[usize]) -> usize {
     panic!("not found");
 }
 
-const P1: usize = 466456641;
-
-#[aoc(day9, part2)]
-pub fn part2(entries: &[usize]) -> usize {
+// min + max of the first contiguous set of at least two numbers that adds up to target
+pub fn weakness(entries: &[usize], target: usize) -> usize {
     for start_idx in 0..entries.len() {
         let start: usize = *entries.get(start_idx).unwrap();
         let mut min = start;
@@ -67,14 +71,62 @@ pub fn part2(entries: &[usize]) -> usize {
                 min = cur;
             }
 
-            if sum == P1 {
+            if sum == target {
                 return min + max;
             }
 
-            if sum > P1 {
+            if sum > target {
                 break;
             }
         }
     }
     panic!("set not found");
 }
+
+#[aoc(day9, part1)]
+pub fn part1(entries: &[usize]) -> usize {
+    first_invalid(entries, PREAMBLE_SIZE)
+}
+
+#[aoc(day9, part2)]
+pub fn part2(entries: &[usize]) -> usize {
+    weakness(entries, part1(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn p1_t1() {
+        let entries = parse_input(SAMPLE_1);
+        assert_eq!(127, first_invalid(&entries, 5));
+    }
+
+    #[test]
+    fn p2_t1() {
+        let entries = parse_input(SAMPLE_1);
+        assert_eq!(62, weakness(&entries, 127));
+    }
+
+    const SAMPLE_1: &str = "35
+20
+15
+25
+47
+40
+62
+55
+65
+95
+102
+117
+150
+182
+127
+219
+299
+277
+309
+576";
+}
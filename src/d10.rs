@@ -1,17 +1,20 @@
 use itertools::sorted;
 
+use crate::error::{parse_numbers, ParseError};
+
 #[aoc_generator(day10)]
 pub fn parse_input(input: &str) -> Vec<usize> {
-    let mut entries = input
-        .lines()
-        .map(|line| line.parse::<usize>().unwrap())
-        .collect::<Vec<usize>>();
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<Vec<usize>, ParseError> {
+    let mut entries: Vec<usize> = parse_numbers(10, input)?;
 
     // add in the first and last values (not in input file...)
     entries.push(0);
     entries.push(entries.iter().max().unwrap() + 3);
 
-    sorted(entries).as_slice().to_vec()
+    Ok(sorted(entries).as_slice().to_vec())
 }
 
 #[aoc(day10, part1)]
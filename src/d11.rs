@@ -1,13 +1,9 @@
-const DIRECTIONS: [(isize, isize); 8] = [
-    (-1, -1),
-    (-1, 0),
-    (-1, 1),
-    (0, -1),
-    (0, 1),
-    (1, -1),
-    (1, 0),
-    (1, 1),
-];
+use std::fmt::{Display, Formatter};
+
+use crate::automaton::{Automaton, Bounded};
+use crate::error::ParseError;
+use crate::grid::Grid;
+use crate::render::{self, Frame};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Tile {
@@ -17,179 +13,94 @@ pub enum Tile {
 }
 
 impl Tile {
-    fn from_char(c: char) -> Self {
+    fn from_char(c: char) -> Option<Self> {
         match c {
-            '.' => Tile::Floor,
-            'L' => Tile::Empty,
-            '#' => Tile::Occupied,
-            _ => panic!("unknown input"),
+            '.' => Some(Tile::Floor),
+            'L' => Some(Tile::Empty),
+            '#' => Some(Tile::Occupied),
+            _ => None,
         }
     }
 }
 
-fn step_adjacent(tiles: &[Vec<Tile>]) -> Option<Vec<Vec<Tile>>> {
-    let mut changed = false;
-    let mut new_tiles: Vec<Vec<Tile>> = tiles.to_owned();
-
-    for y in 0..tiles.len() {
-        for x in 0..tiles[0].len() {
-            match tiles[y][x] {
-                Tile::Floor => {}
-                Tile::Empty => {
-                    // If a seat is empty (L) and there are no occupied seats adjacent to it, the seat becomes occupied.
-                    if count_occupied_adjacent(tiles, x, y) == 0 {
-                        changed = true;
-                        new_tiles[y][x] = Tile::Occupied;
-                    }
-                }
-                Tile::Occupied => {
-                    // If a seat is occupied (#) and four or more seats adjacent to it are also occupied, the seat becomes empty.
-                    if count_occupied_adjacent(&tiles, x, y) >= 4 {
-                        changed = true;
-                        new_tiles[y][x] = Tile::Empty;
-                    }
-                }
-            }
-        }
-    }
-
-    if changed {
-        Some(new_tiles)
-    } else {
-        None
+impl Display for Tile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let c = match self {
+            Tile::Floor => '.',
+            Tile::Empty => 'L',
+            Tile::Occupied => '#',
+        };
+        write!(f, "{}", c)
     }
 }
 
-fn count_occupied_adjacent(tiles: &[Vec<Tile>], x: usize, y: usize) -> usize {
-    let height = tiles.len();
-    let width = tiles[0].len();
-
-    let mut count = 0;
-
-    for (dx, dy) in DIRECTIONS.iter() {
-        let new_x = (x as isize) + dx;
-        let new_y = (y as isize) + dy;
-
-        if new_x >= 0
-            && (new_x as usize) < width
-            && new_y >= 0
-            && (new_y as usize) < height
-            && tiles[new_y as usize][new_x as usize] == Tile::Occupied
-        {
-            count += 1;
-        }
-    }
-
-    count
+// floor never changes, so only the seats take part
+fn seats(tiles: &Grid<Tile>) -> (Grid<bool>, Vec<(usize, usize)>) {
+    let mask = tiles.map(|tile| *tile != Tile::Floor);
+    let occupied = tiles
+        .iter()
+        .filter(|(_pos, tile)| **tile == Tile::Occupied)
+        .map(|(pos, _tile)| pos)
+        .collect();
+    (mask, occupied)
 }
 
-fn step_seen(tiles: &[Vec<Tile>]) -> Option<Vec<Vec<Tile>>> {
-    let mut changed = false;
-    let mut new_tiles: Vec<Vec<Tile>> = tiles.to_owned();
-
-    for y in 0..tiles.len() {
-        for x in 0..tiles[0].len() {
-            match tiles[y][x] {
-                Tile::Floor => {}
-                Tile::Empty => {
-                    // If a seat is empty (L) and there are no occupied seats adjacent to it, the seat becomes occupied.
-                    if count_occupied_seen(&tiles, x, y) == 0 {
-                        changed = true;
-                        new_tiles[y][x] = Tile::Occupied;
-                    }
-                }
-                Tile::Occupied => {
-                    // If a seat is occupied (#) and four or more seats adjacent to it are also occupied, the seat becomes empty.
-                    if count_occupied_seen(&tiles, x, y) >= 5 {
-                        changed = true;
-                        new_tiles[y][x] = Tile::Empty;
-                    }
-                }
-            }
-        }
-    }
-
-    if changed {
-        Some(new_tiles)
-    } else {
-        None
-    }
+#[aoc_generator(day11)]
+pub fn parse_input(input: &str) -> Grid<Tile> {
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
 }
 
-fn count_occupied_seen(tiles: &[Vec<Tile>], x: usize, y: usize) -> usize {
-    let height = tiles.len();
-    let width = tiles[0].len();
-
-    let mut count = 0;
-    for (dx, dy) in DIRECTIONS.iter() {
-        let mut new_x: isize = (x as isize) + dx;
-        let mut new_y: isize = (y as isize) + dy;
-
-        if new_x < 0 || new_y < 0 {
-            continue;
-        }
-
-        while (new_x as usize) < width && (new_y as usize) < height {
-            match tiles[new_y as usize][new_x as usize] {
-                Tile::Floor => {
-                    new_x += dx;
-                    new_y += dy;
-                }
-                Tile::Empty => break,
-                Tile::Occupied => {
-                    count += 1;
-                    break;
-                }
-            }
-        }
-    }
-    count
+pub fn try_parse_input(input: &str) -> Result<Grid<Tile>, ParseError> {
+    Grid::parse(11, input, Tile::from_char)
 }
 
-fn total_occupied_tiles(tiles: &[Vec<Tile>]) -> usize {
-    let mut count = 0;
-    for row in tiles {
-        for tile in row {
-            if tile == &Tile::Occupied {
-                count += 1;
-            }
-        }
-    }
-    count
+// An empty seat with no occupied seats around it becomes occupied, an occupied seat with
+// four or more (five or more looking past the floor) becomes empty. f sees every round.
+fn simulate<F>(tiles: &Grid<Tile>, part: u32, f: F) -> usize
+where
+    F: FnMut(&Automaton<Bounded>),
+{
+    let (mask, occupied) = seats(tiles);
+    let (topology, rule) = if part == 1 {
+        (Bounded::adjacent(&mask), "B0/S0123")
+    } else {
+        (Bounded::line_of_sight(&mask), "B0/S01234")
+    };
+    Automaton::new(topology, rule.parse().unwrap(), occupied).run_until_stable_with(f)
 }
 
-#[aoc_generator(day11)]
-pub fn parse_input(input: &str) -> Vec<Vec<Tile>> {
-    input
-        .lines()
-        .map(|line| line.chars().map(Tile::from_char).collect::<Vec<Tile>>())
-        .collect::<Vec<Vec<Tile>>>()
+// one frame per round until the seats settle
+pub fn frames(tiles: &Grid<Tile>, part: u32) -> Vec<Frame> {
+    let mut frames = Vec::new();
+    simulate(tiles, part, |seats| {
+        let cells = tiles
+            .iter()
+            .map(|(pos, tile)| match tile {
+                Tile::Floor => Tile::Floor,
+                _ if seats.is_alive(&pos) => Tile::Occupied,
+                _ => Tile::Empty,
+            })
+            .collect();
+        let round = Grid::from_cells(tiles.width(), cells);
+        let image = render::cells_image(&round, 4, |tile| match tile {
+            Tile::Floor => render::BACKGROUND,
+            Tile::Empty => [70, 160, 90],
+            Tile::Occupied => [220, 70, 60],
+        });
+        frames.push(Frame {
+            text: round.to_string(),
+            image,
+        });
+    });
+    frames
 }
 
 #[aoc(day11, part1)]
-pub fn part1(tiles: &[Vec<Tile>]) -> usize {
-    let map = tiles.to_vec();
-    let mut result = Some(map);
-    while result != None {
-        let map = result.unwrap();
-        result = step_adjacent(&map);
-        if result == None {
-            return total_occupied_tiles(&map);
-        }
-    }
-    panic!("count not found");
+pub fn part1(tiles: &Grid<Tile>) -> usize {
+    simulate(tiles, 1, |_| {})
 }
 
 #[aoc(day11, part2)]
-pub fn part2(tiles: &[Vec<Tile>]) -> usize {
-    let map = tiles.to_vec();
-    let mut result = Some(map);
-    while result != None {
-        let map = result.unwrap();
-        result = step_seen(&map);
-        if result == None {
-            return total_occupied_tiles(&map);
-        }
-    }
-    panic!("count not found");
+pub fn part2(tiles: &Grid<Tile>) -> usize {
+    simulate(tiles, 2, |_| {})
 }
@@ -1,3 +1,7 @@
+use crate::error::ParseError;
+use crate::grid::{Grid, ADJACENT};
+use crate::render::{self, Frame};
+
 #[derive(Debug, Copy, Clone)]
 pub struct Point {
     x: i32,
@@ -14,7 +18,7 @@ impl Point {
     }
 
     fn rotate(&mut self, degrees: i32) {
-        match (degrees + 360) % 360 {
+        match degrees.rem_euclid(360) {
             0 => {}
             90 => {
                 let temp = self.x;
@@ -59,20 +63,22 @@ pub enum Instruction {
 }
 
 impl Instruction {
-    fn from_str(s: &str) -> Self {
-        if let Ok((key, amount)) = scan_fmt!(s, "{[NSWEFLR]}{d}", char, i32) {
-            return match key {
-                'N' => Instruction::Translation(Point::new(0, amount)),
-                'S' => Instruction::Translation(Point::new(0, -amount)),
-                'E' => Instruction::Translation(Point::new(amount, 0)),
-                'W' => Instruction::Translation(Point::new(-amount, 0)),
-                'F' => Instruction::Move(amount),
-                'L' => Instruction::Rotation(amount),
-                'R' => Instruction::Rotation(-amount),
-                _ => panic!("invalid instruction"),
-            };
+    fn from_str(s: &str) -> Result<Self, &'static str> {
+        let (key, amount) = scan_fmt!(s, "{[NSWEFLR]}{d}", char, i32)
+            .map_err(|_| "expected an action N/S/E/W/F/L/R and a number")?;
+        // Point::rotate only handles right angles
+        if (key == 'L' || key == 'R') && amount % 90 != 0 {
+            return Err("rotation must be a multiple of 90");
         }
-        panic!("unable to parse");
+        Ok(match key {
+            'N' => Instruction::Translation(Point::new(0, amount)),
+            'S' => Instruction::Translation(Point::new(0, -amount)),
+            'E' => Instruction::Translation(Point::new(amount, 0)),
+            'W' => Instruction::Translation(Point::new(-amount, 0)),
+            'F' => Instruction::Move(amount),
+            'L' => Instruction::Rotation(amount),
+            _ => Instruction::Rotation(-amount),
+        })
     }
 }
 
@@ -110,23 +116,106 @@ impl Ship {
 
 #[aoc_generator(day12)]
 pub fn parse_input(input: &str) -> Vec<Instruction> {
-    input.lines().map(Instruction::from_str).collect()
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
 }
 
-#[aoc(day12, part1)]
-pub fn part1(instructions: &[Instruction]) -> i32 {
+pub fn try_parse_input(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    input
+        .lines()
+        .map(|line| {
+            Instruction::from_str(line).map_err(|message| ParseError::at(12, input, line, message))
+        })
+        .collect()
+}
+
+// f sees the ship before the first instruction and after every one
+fn sail<F: FnMut(&Ship)>(instructions: &[Instruction], part: u32, mut f: F) -> Ship {
     let mut ship = Ship::new();
+    f(&ship);
+    for instruction in instructions {
+        if part == 1 {
+            ship.execute_p1(instruction);
+        } else {
+            ship.execute_p2(instruction);
+        }
+        f(&ship);
+    }
+    ship
+}
 
-    instructions.iter().for_each(|i| ship.execute_p1(i));
+// The route so far after every instruction, north up. The image is at most 400 pixels across
+// and the text 60 columns, with half as many rows since characters are about twice as tall.
+pub fn frames(instructions: &[Instruction], part: u32) -> Vec<Frame> {
+    let mut steps = Vec::new();
+    sail(instructions, part, |ship| {
+        let heading = if part == 1 {
+            ship.facing
+        } else {
+            ship.waypoint
+        };
+        steps.push((ship.pos, heading));
+    });
+
+    let (mut min, mut max) = (Point::new(0, 0), Point::new(0, 0));
+    for (pos, _) in &steps {
+        min = Point::new(min.x.min(pos.x), min.y.min(pos.y));
+        max = Point::new(max.x.max(pos.x), max.y.max(pos.y));
+    }
+    let span = (max.x - min.x).max(max.y - min.y).max(1) as f64;
+    // the size of a canvas and where points go on it
+    let canvas = |columns: f64, aspect: f64| {
+        let scale = (columns - 1.0) / span;
+        let width = ((max.x - min.x) as f64 * scale) as usize + 1;
+        let height = ((max.y - min.y) as f64 * scale * aspect) as usize + 1;
+        let to_canvas = move |p: Point| {
+            let x = (p.x - min.x) as f64 * scale;
+            let y = (max.y - p.y) as f64 * scale * aspect;
+            ((x as usize).min(width - 1), (y as usize).min(height - 1))
+        };
+        (width, height, to_canvas)
+    };
+    let (width, height, text_at) = canvas(60.0, 0.5);
+    let mut text = Grid::new(width, height, '.');
+    let (width, height, image_at) = canvas(400.0, 1.0);
+    let mut image = Grid::new(width, height, render::BACKGROUND);
+
+    let mut frames = Vec::new();
+    for (i, (pos, heading)) in steps.iter().enumerate() {
+        if i > 0 {
+            let from = steps[i - 1].0;
+            render::draw_line(&mut text, text_at(from), text_at(*pos), '#');
+            render::draw_line(&mut image, image_at(from), image_at(*pos), [90, 160, 230]);
+        }
 
-    ship.pos.manhattan_distance()
+        let mut text = text.clone();
+        text[text_at(steps[0].0)] = 'S';
+        text[text_at(*pos)] = '@';
+        let mut image = image.clone();
+        let (x, y) = image_at(*pos);
+        for (dx, dy) in ADJACENT.iter().chain(&[(0, 0)]) {
+            if let Some(pos) = image.offset(x, y, *dx, *dy) {
+                image[pos] = [230, 80, 60];
+            }
+        }
+
+        let label = if part == 1 { "facing" } else { "waypoint" };
+        frames.push(Frame {
+            text: format!(
+                "ship at ({}, {}), {} ({}, {})\n{}",
+                pos.x, pos.y, label, heading.x, heading.y, text
+            ),
+            image,
+        });
+    }
+    frames
+}
+
+#[aoc(day12, part1)]
+pub fn part1(instructions: &[Instruction]) -> i32 {
+    sail(instructions, 1, |_| {}).pos.manhattan_distance()
 }
 
 #[aoc(day12, part2)]
 pub fn part2(instructions: &[Instruction]) -> i32 {
-    let mut ship = Ship::new();
-
-    instructions.iter().for_each(|i| ship.execute_p2(i));
-
-    ship.pos.manhattan_distance()
+    sail(instructions, 2, |_| {}).pos.manhattan_distance()
 }
@@ -1,3 +1,5 @@
+use crate::error::ParseError;
+
 pub struct D13Input {
     earliest_depart: i64,
     busses: Vec<Bus>,
@@ -10,15 +12,29 @@ pub struct Bus {
 
 #[aoc_generator(day13)]
 pub fn parse_input(input: &str) -> D13Input {
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<D13Input, ParseError> {
     let mut split = input.split('\n');
 
-    let earliest_depart = split.next().unwrap().parse::<i64>().unwrap();
+    let first = split.next().unwrap();
+    let earliest_depart = first
+        .parse::<i64>()
+        .map_err(|_| ParseError::at(13, input, first, "expected a departure time"))?;
 
+    let busses_str = split
+        .next()
+        .ok_or_else(|| ParseError::input(13, "missing bus list"))?;
     let mut busses: Vec<Bus> = Vec::new();
 
-    for (i, s) in split.next().unwrap().split(',').enumerate() {
+    for (i, s) in busses_str.split(',').enumerate() {
         if s != "x" {
-            let id = s.parse::<i64>().unwrap();
+            let id = s
+                .parse::<i64>()
+                .ok()
+                .filter(|id| *id > 0)
+                .ok_or_else(|| ParseError::at(13, input, s, "expected a bus id or x"))?;
 
             let mut remainder = (id - i as i64) % id;
             while remainder < 0 {
@@ -28,15 +44,15 @@ pub fn parse_input(input: &str) -> D13Input {
         }
     }
 
-    D13Input {
+    Ok(D13Input {
         earliest_depart,
         busses,
-    }
+    })
 }
 
 #[aoc(day13, part1)]
 pub fn part1(input: &D13Input) -> i64 {
-    let mut min_wait = i64::max_value();
+    let mut min_wait = i64::MAX;
     let mut min_id = -1;
 
     for bus in &input.busses {
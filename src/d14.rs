@@ -1,6 +1,8 @@
 use fancy_regex::Regex;
 use std::collections::HashMap;
 
+use crate::error::ParseError;
+
 #[derive(Debug)]
 pub enum Instruction {
     Mask(String),
@@ -8,21 +10,28 @@ pub enum Instruction {
 }
 
 impl Instruction {
-    fn from_str(s: &str) -> Self {
-        let re_mask = Regex::new(r"^mask = (.{36})$").unwrap();
-        let re_mem = Regex::new(r"^mem\[(\d*)] = (\d*)$").unwrap();
+    // on error, returns the offending part of s and what is wrong with it
+    fn from_str(s: &str) -> Result<Self, (&str, &'static str)> {
+        let re_mask = Regex::new(r"^mask = ([X01]{36})$").unwrap();
+        let re_mem = Regex::new(r"^mem\[(\d+)] = (\d+)$").unwrap();
 
-        if re_mask.is_match(s).unwrap() {
-            let captures = re_mask.captures(s).unwrap().unwrap();
+        if let Some(captures) = re_mask.captures(s).unwrap() {
             let mask = captures.get(1).unwrap().as_str().to_string();
-            return Instruction::Mask(mask);
-        } else if re_mem.is_match(s).unwrap() {
-            let captures = re_mem.captures(s).unwrap().unwrap();
-            let index = captures.get(1).unwrap().as_str().parse::<usize>().unwrap();
-            let val = captures.get(2).unwrap().as_str().parse::<usize>().unwrap();
-            return Instruction::Mem(index, val);
+            return Ok(Instruction::Mask(mask));
+        } else if let Some(captures) = re_mem.captures(s).unwrap() {
+            let number = |i| {
+                let text = captures.get(i).unwrap().as_str();
+                text.parse::<usize>()
+                    .ok()
+                    .filter(|n| *n < 1 << 36)
+                    .ok_or((text, "expected a 36 bit number"))
+            };
+            return Ok(Instruction::Mem(number(1)?, number(2)?));
         }
-        panic!("invalid format")
+        Err((
+            s,
+            "expected `mask = <36 of X/0/1>` or `mem[<address>] = <value>`",
+        ))
     }
 }
 
@@ -47,16 +56,12 @@ impl Cpu {
                 let vstr = format!("{:036b}", v);
 
                 let mut val_string = String::new();
-                self.mask
-                    .chars()
-                    .into_iter()
-                    .zip(vstr.chars().into_iter())
-                    .for_each(|i| match i.0 {
-                        'X' => val_string = format!("{}{}", val_string, i.1),
-                        '1' => val_string = format!("{}{}", val_string, 1),
-                        '0' => val_string = format!("{}{}", val_string, 0),
-                        _ => panic!("invalid mask"),
-                    });
+                self.mask.chars().zip(vstr.chars()).for_each(|i| match i.0 {
+                    'X' => val_string = format!("{}{}", val_string, i.1),
+                    '1' => val_string = format!("{}{}", val_string, 1),
+                    '0' => val_string = format!("{}{}", val_string, 0),
+                    _ => panic!("invalid mask"),
+                });
                 self.mem
                     .insert(*i, usize::from_str_radix(val_string.as_str(), 2).unwrap());
             }
@@ -80,38 +85,35 @@ fn apply_mask(mask: String, mem: usize) -> Vec<usize> {
 
     let mut addresses: Vec<String> = vec!["".to_string()];
 
-    mask.chars()
-        .into_iter()
-        .zip(memstr.chars().into_iter())
-        .for_each(|i| match i.0 {
-            '0' => {
-                let mut new_addresses: Vec<String> = Vec::new();
-                for a in &addresses {
-                    let new_a = format!("{}{}", a, i.1);
-                    new_addresses.push(new_a);
-                }
-                addresses = new_addresses;
+    mask.chars().zip(memstr.chars()).for_each(|i| match i.0 {
+        '0' => {
+            let mut new_addresses: Vec<String> = Vec::new();
+            for a in &addresses {
+                let new_a = format!("{}{}", a, i.1);
+                new_addresses.push(new_a);
             }
-            '1' => {
-                let mut new_addresses: Vec<String> = Vec::new();
-                for a in &addresses {
-                    let new_a = format!("{}{}", a, 1);
-                    new_addresses.push(new_a);
-                }
-                addresses = new_addresses;
+            addresses = new_addresses;
+        }
+        '1' => {
+            let mut new_addresses: Vec<String> = Vec::new();
+            for a in &addresses {
+                let new_a = format!("{}{}", a, 1);
+                new_addresses.push(new_a);
             }
-            'X' => {
-                let mut new_addresses: Vec<String> = Vec::new();
-                for a in &addresses {
-                    let new_a_0 = format!("{}{}", a, 0);
-                    let new_a_1 = format!("{}{}", a, 1);
-                    new_addresses.push(new_a_0);
-                    new_addresses.push(new_a_1);
-                }
-                addresses = new_addresses;
+            addresses = new_addresses;
+        }
+        'X' => {
+            let mut new_addresses: Vec<String> = Vec::new();
+            for a in &addresses {
+                let new_a_0 = format!("{}{}", a, 0);
+                let new_a_1 = format!("{}{}", a, 1);
+                new_addresses.push(new_a_0);
+                new_addresses.push(new_a_1);
             }
-            _ => panic!("unknown mask"),
-        });
+            addresses = new_addresses;
+        }
+        _ => panic!("unknown mask"),
+    });
 
     let mut memory_addresses: Vec<usize> = Vec::new();
     for mem in addresses {
@@ -122,7 +124,17 @@ fn apply_mask(mask: String, mem: usize) -> Vec<usize> {
 
 #[aoc_generator(day14)]
 pub fn parse_input(input: &str) -> Vec<Instruction> {
-    input.lines().map(Instruction::from_str).collect()
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    input
+        .lines()
+        .map(|line| {
+            Instruction::from_str(line)
+                .map_err(|(text, message)| ParseError::at(14, input, text, message))
+        })
+        .collect()
 }
 
 #[aoc(day14, part1)]
@@ -1,10 +1,20 @@
 use std::collections::HashMap;
 
+use crate::error::ParseError;
+
 #[aoc_generator(day15)]
 pub fn parse_input(input: &str) -> Vec<usize> {
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<Vec<usize>, ParseError> {
     input
         .split(',')
-        .map(|s| s.parse::<usize>().unwrap())
+        .map(|s| {
+            s.trim()
+                .parse::<usize>()
+                .map_err(|_| ParseError::at(15, input, s, "expected a number"))
+        })
         .collect()
 }
 
@@ -25,7 +35,7 @@ fn memory_game(seed: &[usize], nth: usize) -> usize {
         last_said_map.insert(*i.1, i.0);
     });
 
-    let mut most_recent_said: usize = *seed.get(seed.len() - 1).unwrap();
+    let mut most_recent_said: usize = *seed.last().unwrap();
     last_said_map.remove(&most_recent_said); // because of where we add to the map
 
     for i in seed.len() - 1..nth - 1 {
@@ -2,6 +2,8 @@ use std::collections::{HashMap, HashSet};
 
 use fancy_regex::Regex;
 
+use crate::error::ParseError;
+
 #[derive(Debug)]
 pub struct D16Input {
     rules: HashMap<String, ValidRanges>,
@@ -19,49 +21,74 @@ pub struct ValidRanges {
 
 #[aoc_generator(day16)]
 pub fn parse_input(input: &str) -> D16Input {
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+fn parse_ticket(input: &str, line: &str) -> Result<Vec<usize>, ParseError> {
+    line.split(',')
+        .map(|s| {
+            s.parse::<usize>()
+                .map_err(|_| ParseError::at(16, input, s, "expected a number"))
+        })
+        .collect()
+}
+
+// the lines of a section after its header
+fn section<'a>(
+    input: &'a str,
+    section: Option<&'a str>,
+    header: &str,
+) -> Result<std::str::Split<'a, char>, ParseError> {
+    let section = section.ok_or_else(|| ParseError::input(16, format!("missing {}", header)))?;
+    let mut lines = section.split('\n');
+    let first = lines.next().unwrap();
+    if first != format!("{}:", header) {
+        let message = format!("expected `{}:`", header);
+        return Err(ParseError::at(16, input, first, message));
+    }
+    Ok(lines)
+}
+
+pub fn try_parse_input(input: &str) -> Result<D16Input, ParseError> {
     let mut sections = input.split("\n\n");
 
     let re_rules = Regex::new(r"^([\w\s]*): (\d+)-(\d+) or (\d+)-(\d+)$").unwrap();
     let mut rules: HashMap<String, ValidRanges> = HashMap::new();
-    sections.next().unwrap().lines().for_each(|line| {
-        let captures = re_rules.captures(line).unwrap().unwrap();
+    for line in sections.next().unwrap().lines() {
+        let captures = re_rules.captures(line).unwrap().ok_or_else(|| {
+            ParseError::at(16, input, line, "expected `<field>: <a>-<b> or <c>-<d>`")
+        })?;
         let field = captures.get(1).unwrap().as_str().to_string();
-        let min1 = captures.get(2).unwrap().as_str().parse::<usize>().unwrap();
-        let max1 = captures.get(3).unwrap().as_str().parse::<usize>().unwrap();
-        let min2 = captures.get(4).unwrap().as_str().parse::<usize>().unwrap();
-        let max2 = captures.get(5).unwrap().as_str().parse::<usize>().unwrap();
+        let number = |i| {
+            let text = captures.get(i).unwrap().as_str();
+            text.parse::<usize>()
+                .map_err(|_| ParseError::at(16, input, text, "number out of range"))
+        };
 
         let vr = ValidRanges {
-            min1,
-            max1,
-            min2,
-            max2,
+            min1: number(2)?,
+            max1: number(3)?,
+            min2: number(4)?,
+            max2: number(5)?,
         };
         rules.insert(field, vr);
-    });
-
-    let my_ticket: Vec<usize> = sections
-        .next()
-        .unwrap()
-        .split('\n')
-        .nth(1) // skip the header
-        .unwrap()
-        .split(',')
-        .map(|s| s.parse::<usize>().unwrap())
-        .collect();
-
-    let mut nearby_split = sections.next().unwrap().split('\n');
-    nearby_split.next(); //skip the header
-
-    let nearby_tickets: Vec<Vec<usize>> = nearby_split
-        .map(|s| s.split(',').map(|t| t.parse::<usize>().unwrap()).collect())
-        .collect();
-
-    D16Input {
+    }
+
+    let mut my_lines = section(input, sections.next(), "your ticket")?;
+    let my_ticket = match my_lines.next() {
+        Some(line) => parse_ticket(input, line)?,
+        None => return Err(ParseError::input(16, "missing your ticket")),
+    };
+
+    let nearby_tickets: Vec<Vec<usize>> = section(input, sections.next(), "nearby tickets")?
+        .map(|line| parse_ticket(input, line))
+        .collect::<Result<_, _>>()?;
+
+    Ok(D16Input {
         rules,
         my_ticket,
         nearby_tickets,
-    }
+    })
 }
 
 fn valid_column(val: &usize, rule: &ValidRanges) -> bool {
@@ -167,7 +194,7 @@ pub fn part2(input: &D16Input) -> usize {
 
             // if there is only one rule possible for this column, it is solved
             if valid_possible.len() == 1 {
-                let rule_name = valid_possible.get(0).unwrap();
+                let rule_name = valid_possible.first().unwrap();
                 solved_rules.insert(rule_name.clone(), index); // save the solved rule
                 rules_to_solve.remove(rule_name); // remove it from possible
             }
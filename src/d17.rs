@@ -1,241 +1,131 @@
-use std::collections::{HashMap, HashSet};
-use std::hash::Hash;
-
-fn parse_input_3d(input: &str, width: usize, height: usize) -> Conway<Coord3D> {
-    let lines = input
-        .lines()
-        .map(|l| l.to_string())
-        .collect::<Vec<String>>();
-
-    let mut coords = Vec::new();
-    for y in 0..height {
-        let mut line = lines.get(y).unwrap().chars();
-        for x in 0..width {
-            let c = line.next().unwrap();
-            let coord = Coord3D::new(x as i32, y as i32, 0);
-            if c == '#' {
-                coords.push((coord, State::Active));
-            } else {
-                coords.push((coord, State::Inactive));
-            }
-        }
-    }
-
-    let mut cube: Conway<Coord3D> = Conway::new();
-    for (coord, state) in coords {
-        cube.coords.insert(coord, state);
-    }
-    cube
+use std::collections::HashSet;
+
+use crate::automaton::{Automaton, Moore};
+use crate::error::ParseError;
+use crate::grid::Grid;
+use crate::render::{self, Frame};
+
+// the starting slice, true for active cubes
+pub fn try_parse_input(input: &str) -> Result<Grid<bool>, ParseError> {
+    Grid::parse(17, input, |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })
 }
 
-fn parse_input_4d(input: &str, width: usize, height: usize) -> Conway<Coord4D> {
-    let lines = input
-        .lines()
-        .map(|l| l.to_string())
-        .collect::<Vec<String>>();
-
-    let mut coords = Vec::new();
-    for y in 0..height {
-        let mut line = lines.get(y).unwrap().chars();
-        for x in 0..width {
-            let c = line.next().unwrap();
-            let coord = Coord4D::new(x as i32, y as i32, 0, 0);
-            if c == '#' {
-                coords.push((coord, State::Active));
-            } else {
-                coords.push((coord, State::Inactive));
-            }
-        }
-    }
-
-    let mut hypercube = Conway::new();
-    for (coord, state) in coords {
-        hypercube.coords.insert(coord, state);
-    }
-    hypercube
+// the active cubes of the seed, in the z = 0 (and w = 0) slice
+fn seed_cells<const N: usize>(seed: &Grid<bool>) -> Vec<[i32; N]> {
+    seed.iter()
+        .filter(|(_pos, active)| **active)
+        .map(|((x, y), _active)| {
+            let mut cell = [0; N];
+            cell[0] = x as i32;
+            cell[1] = y as i32;
+            cell
+        })
+        .collect()
 }
 
-//-------------------------------------------------------------------------------------------------
-
-trait Coord {
-    fn get_adjacent(&self) -> Vec<Self>
-    where
-        Self: Sized;
-}
-
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum State {
-    Active,
-    Inactive,
-}
-
-struct Conway<T>
+// f sees the cubes before the first cycle and after every one
+fn boot<const N: usize, F>(seed: &Grid<bool>, f: F) -> usize
 where
-    T: Hash,
-    T: Eq,
-    T: Coord,
-    T: Clone,
+    F: FnMut(&Automaton<Moore<N>>),
 {
-    coords: HashMap<T, State>,
+    let rule = "B3/S23".parse().unwrap();
+    Automaton::new(Moore::<N>, rule, seed_cells::<N>(seed)).run_with(6, f)
 }
 
-impl<T> Conway<T>
-where
-    T: Hash,
-    T: Eq,
-    T: Coord,
-    T: Clone,
-{
-    fn new() -> Self {
-        Self {
-            coords: HashMap::new(),
-        }
-    }
-
-    fn total_active_coords(&self) -> usize {
-        self.coords
-            .values()
-            .filter(|v| **v == State::Active)
-            .count()
-    }
-
-    fn get_coords_to_check(&self) -> HashSet<T> {
-        let mut to_check: HashSet<T> = HashSet::new();
-
-        for coord in self.coords.keys() {
-            to_check.insert(coord.clone());
-            for c in coord.get_adjacent() {
-                to_check.insert(c.clone());
-            }
-        }
-        to_check
-    }
-
-    fn get_coord_state(&self, coord: &T) -> State {
-        if let Some(state) = self.coords.get(&coord) {
-            if *state == State::Active {
-                return State::Active;
-            }
-        }
-        State::Inactive
+pub fn frames(seed: &Grid<bool>, part: u32) -> Vec<Frame> {
+    if part == 1 {
+        slices::<3>(seed)
+    } else {
+        slices::<4>(seed)
     }
-
-    fn count_active_neighbors(&self, origin: &T) -> usize {
-        let mut count: usize = 0;
-        for coord in origin.get_adjacent() {
-            if self.get_coord_state(&coord) == State::Active {
-                count += 1;
-            }
-        }
-        count
-    }
-
-    fn step(&mut self) {
-        let mut new_coords: HashMap<T, State> = HashMap::new();
-
-        for coord in self.get_coords_to_check() {
-            let state = self.get_coord_state(&coord);
-            let active_neighbor_count = self.count_active_neighbors(&coord);
-            match state {
-                State::Active => {
-                    if active_neighbor_count == 2 || active_neighbor_count == 3 {
-                        new_coords.insert(coord, State::Active);
-                    } else {
-                        new_coords.insert(coord, State::Inactive);
-                    }
-                }
-                State::Inactive => {
-                    if active_neighbor_count == 3 {
-                        new_coords.insert(coord, State::Active);
-                    }
-                }
-            }
-        }
-        self.coords = new_coords;
-    }
-
-    fn solve(&mut self) -> usize {
-        for _i in 0..6 {
-            self.step();
-        }
-        self.total_active_coords()
-    }
-}
-
-#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
-struct Coord3D {
-    x: i32,
-    y: i32,
-    z: i32,
 }
 
-impl Coord3D {
-    fn new(x: i32, y: i32, z: i32) -> Self {
-        Self { x, y, z }
-    }
-}
-
-impl Coord for Coord3D {
-    fn get_adjacent(&self) -> Vec<Self>
-    where
-        Self: Sized,
-    {
-        let mut coords: Vec<Self> = Vec::new();
-        for x in -1..2 {
-            for y in -1..2 {
-                for z in -1..2 {
-                    if x == 0 && y == 0 && z == 0 {
-                        continue;
-                    }
-                    coords.push(Self::new(self.x + x, self.y + y, self.z + z));
-                }
-            }
+// every cycle as its z slices side by side, and in 4d a row of them for each w
+fn slices<const N: usize>(seed: &Grid<bool>) -> Vec<Frame> {
+    let mut cycles: Vec<HashSet<[i32; N]>> = Vec::new();
+    boot::<N, _>(seed, |cubes| cycles.push(cubes.live().copied().collect()));
+
+    // the same bounds for every frame so that they line up
+    let (mut min, mut max) = ([0; N], [0; N]);
+    for cube in cycles.iter().flatten() {
+        for d in 0..N {
+            min[d] = min[d].min(cube[d]);
+            max[d] = max[d].max(cube[d]);
         }
-        coords
-    }
-}
-
-#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
-struct Coord4D {
-    x: i32,
-    y: i32,
-    z: i32,
-    w: i32,
-}
-
-impl Coord4D {
-    fn new(x: i32, y: i32, z: i32, w: i32) -> Self {
-        Self { x, y, z, w }
     }
-}
-
-impl Coord for Coord4D {
-    fn get_adjacent(&self) -> Vec<Self> {
-        let mut coords: Vec<Self> = Vec::new();
-        for x in -1..2 {
-            for y in -1..2 {
-                for z in -1..2 {
-                    for w in -1..2 {
-                        if x == 0 && y == 0 && z == 0 && w == 0 {
-                            continue;
+    let width = (max[0] - min[0] + 1) as usize;
+    let height = (max[1] - min[1] + 1) as usize;
+    let zs: Vec<i32> = (min[2]..=max[2]).collect();
+    let ws: Vec<i32> =
+        (min.get(3).copied().unwrap_or(0)..=max.get(3).copied().unwrap_or(0)).collect();
+
+    let scale = 4;
+    cycles
+        .iter()
+        .map(|live| {
+            let mut text = Vec::new();
+            let mut image = Grid::new(
+                ((width + 1) * zs.len() - 1) * scale,
+                ((height + 1) * ws.len() - 1) * scale,
+                render::BACKGROUND,
+            );
+            for (row, w) in ws.iter().enumerate() {
+                for (col, z) in zs.iter().enumerate() {
+                    let cells = (0..width * height)
+                        .map(|i| {
+                            let mut cell = [0; N];
+                            cell[0] = min[0] + (i % width) as i32;
+                            cell[1] = min[1] + (i / width) as i32;
+                            cell[2] = *z;
+                            if let Some(c) = cell.get_mut(3) {
+                                *c = *w;
+                            }
+                            live.contains(&cell)
+                        })
+                        .collect();
+                    let slice = Grid::from_cells(width, cells);
+
+                    let label = if N > 3 {
+                        format!("z={}, w={}", z, w)
+                    } else {
+                        format!("z={}", z)
+                    };
+                    text.push(format!(
+                        "{}\n{}",
+                        label,
+                        slice.map(|a| if *a { '#' } else { '.' })
+                    ));
+                    let pixels = render::cells_image(&slice, scale, |active| {
+                        if *active {
+                            [240, 200, 60]
+                        } else {
+                            [48, 48, 64]
                         }
-                        coords.push(Self::new(self.x + x, self.y + y, self.z + z, self.w + w));
-                    }
+                    });
+                    let (x, y) = (col * (width + 1) * scale, row * (height + 1) * scale);
+                    render::paste(&mut image, &pixels, x, y);
                 }
             }
-        }
-        coords
-    }
+            Frame {
+                text: text.join("\n\n"),
+                image,
+            }
+        })
+        .collect()
 }
 
 #[aoc(day17, part1)]
 pub fn part1(input: &str) -> usize {
-    let mut conway3d = parse_input_3d(input, 8, 8);
-    conway3d.solve()
+    let seed = try_parse_input(input).unwrap_or_else(|e| panic!("{}", e));
+    boot::<3, _>(&seed, |_| {})
 }
 
 #[aoc(day17, part2)]
 pub fn part2(input: &str) -> usize {
-    let mut conway4d = parse_input_4d(input, 8, 8);
-    conway4d.solve()
+    let seed = try_parse_input(input).unwrap_or_else(|e| panic!("{}", e));
+    boot::<4, _>(&seed, |_| {})
 }
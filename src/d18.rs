@@ -1,3 +1,5 @@
+use crate::error::{char_at, ParseError};
+
 #[derive(Clone, Copy)]
 enum PrecedenceMode {
     Equal,
@@ -139,7 +141,40 @@ fn solve_rpn(tokens: &[Token]) -> u64 {
 
 #[aoc_generator(day18)]
 pub fn parse_input(input: &str) -> Vec<String> {
-    input.lines().map(|s| s.to_string()).collect()
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+// checks each equation is well formed, the tokenizer only handles single digit numbers
+fn validate(input: &str, line: &str) -> Result<(), ParseError> {
+    let mut depth = 0;
+    let mut expect_operand = true;
+    for (i, c) in line.char_indices().filter(|(_i, c)| !c.is_whitespace()) {
+        let error = |message| Err(ParseError::at(18, input, char_at(line, i), message));
+        match (expect_operand, c) {
+            (true, '0'..='9') => expect_operand = false,
+            (true, '(') => depth += 1,
+            (true, _) => return error("expected a digit or ("),
+            (false, '+') | (false, '*') => expect_operand = true,
+            (false, ')') if depth > 0 => depth -= 1,
+            (false, ')') => return error("unmatched )"),
+            (false, _) => return error("expected +, * or )"),
+        }
+    }
+
+    if expect_operand {
+        Err(ParseError::at(18, input, line, "incomplete equation"))
+    } else if depth > 0 {
+        Err(ParseError::at(18, input, line, "unclosed ("))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn try_parse_input(input: &str) -> Result<Vec<String>, ParseError> {
+    input
+        .lines()
+        .map(|line| validate(input, line).map(|_| line.to_string()))
+        .collect()
 }
 
 #[aoc(day18, part1)]
@@ -0,0 +1,304 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::error::ParseError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    Literal(String),
+    // any one of the sequences of sub rules
+    Alternatives(Vec<Vec<usize>>),
+}
+
+pub struct D19Input {
+    rules: HashMap<usize, Rule>,
+    messages: Vec<String>,
+}
+
+// `<id>: "<literal>"` or `<id>: <sub rules> | <sub rules> ...`
+fn parse_rule(input: &str, line: &str) -> Result<(usize, Rule), ParseError> {
+    let colon = line
+        .find(": ")
+        .ok_or_else(|| ParseError::at(19, input, line, "expected `<id>: <rule>`"))?;
+    let id = &line[..colon];
+    let id = id
+        .parse()
+        .map_err(|_| ParseError::at(19, input, id, "expected a rule number"))?;
+
+    let body = &line[colon + 2..];
+    if let Some(literal) = body.strip_prefix('"') {
+        return match literal.strip_suffix('"') {
+            Some(literal) if !literal.is_empty() => Ok((id, Rule::Literal(literal.to_string()))),
+            _ => Err(ParseError::at(19, input, body, "expected a quoted literal")),
+        };
+    }
+
+    let mut alternatives = Vec::new();
+    for sequence in body.split('|') {
+        let sub_rules = sequence
+            .split_whitespace()
+            .map(|sub_rule| {
+                sub_rule
+                    .parse()
+                    .map_err(|_| ParseError::at(19, input, sub_rule, "expected a rule number"))
+            })
+            .collect::<Result<Vec<usize>, ParseError>>()?;
+        if sub_rules.is_empty() {
+            return Err(ParseError::at(19, input, body, "empty alternative"));
+        }
+        alternatives.push(sub_rules);
+    }
+    Ok((id, Rule::Alternatives(alternatives)))
+}
+
+fn parse_rules<'a>(
+    input: &'a str,
+    lines: impl Iterator<Item = &'a str>,
+) -> Result<HashMap<usize, Rule>, ParseError> {
+    let mut rules = HashMap::new();
+    for line in lines {
+        let (id, rule) = parse_rule(input, line)?;
+        if rules.insert(id, rule).is_some() {
+            return Err(ParseError::at(19, input, line, "rule defined twice"));
+        }
+    }
+    Ok(rules)
+}
+
+#[aoc_generator(day19)]
+pub fn parse_input(input: &str) -> D19Input {
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<D19Input, ParseError> {
+    let mut sections = input.splitn(2, "\n\n");
+    let rules = parse_rules(input, sections.next().unwrap().lines())?;
+    let messages = sections
+        .next()
+        .ok_or_else(|| ParseError::input(19, "missing messages"))?
+        .lines()
+        .map(|line| line.to_string())
+        .collect();
+
+    if !rules.contains_key(&0) {
+        return Err(ParseError::input(19, "missing rule 0"));
+    }
+    for rule in rules.values() {
+        if let Rule::Alternatives(alternatives) = rule {
+            if let Some(id) = alternatives
+                .iter()
+                .flatten()
+                .find(|id| !rules.contains_key(id))
+            {
+                return Err(ParseError::input(19, format!("rule {} is not defined", id)));
+            }
+        }
+    }
+
+    Ok(D19Input { rules, messages })
+}
+
+// The positions where a match of rule `id` starting at `start` can end. Following every
+// alternative rather than the first that matches is what lets recursive rules work: `8: 42 |
+// 42 8` ends after each repetition of 42, and the rule after it is tried from all of them.
+//
+// A rule that comes back to itself before consuming anything (`8: 8 42`, or `0: 1` with `1: 0`)
+// would recurse forever. Instead the inner call gets the ends found for it so far, nothing at
+// first, and `matches` tries again while those grow: `8: 8 42 | 42` ends after one 42, then
+// after two, and so on until no new end turns up.
+struct Matcher<'a> {
+    rules: &'a HashMap<usize, Rule>,
+    message: &'a str,
+    // the rules being matched at each position
+    active: HashSet<(usize, usize)>,
+    // ends found so far for the rules that came back to themselves
+    seeds: HashMap<(usize, usize), Vec<usize>>,
+    grown: bool,
+}
+
+impl Matcher<'_> {
+    fn match_rule(&mut self, id: usize, start: usize) -> Vec<usize> {
+        let alternatives = match &self.rules[&id] {
+            Rule::Literal(literal) => {
+                return if self.message[start..].starts_with(literal.as_str()) {
+                    vec![start + literal.len()]
+                } else {
+                    vec![]
+                };
+            }
+            Rule::Alternatives(alternatives) => alternatives,
+        };
+        if !self.active.insert((id, start)) {
+            return self.seeds.entry((id, start)).or_default().clone();
+        }
+
+        let mut ends = Vec::new();
+        for sequence in alternatives {
+            let mut positions = vec![start];
+            for sub_rule in sequence {
+                let mut next = Vec::new();
+                for position in positions {
+                    // every rule matches at least one character
+                    if position < self.message.len() {
+                        next.extend(self.match_rule(*sub_rule, position));
+                    }
+                }
+                next.sort_unstable();
+                next.dedup();
+                positions = next;
+            }
+            ends.extend(positions);
+        }
+        self.active.remove(&(id, start));
+        ends.sort_unstable();
+        ends.dedup();
+
+        // the ends only grow from one try to the next, there are only so many of them
+        if let Some(seed) = self.seeds.get_mut(&(id, start)) {
+            if ends.iter().any(|end| !seed.contains(end)) {
+                seed.extend(&ends);
+                seed.sort_unstable();
+                seed.dedup();
+                self.grown = true;
+            }
+        }
+        ends
+    }
+}
+
+fn matches(rules: &HashMap<usize, Rule>, message: &str) -> bool {
+    let mut matcher = Matcher {
+        rules,
+        message,
+        active: HashSet::new(),
+        seeds: HashMap::new(),
+        grown: false,
+    };
+    loop {
+        matcher.grown = false;
+        let ends = matcher.match_rule(0, 0);
+        if !matcher.grown {
+            return ends.contains(&message.len());
+        }
+    }
+}
+
+fn count_matches(rules: &HashMap<usize, Rule>, messages: &[String]) -> usize {
+    messages
+        .iter()
+        .filter(|message| matches(rules, message))
+        .count()
+}
+
+#[aoc(day19, part1)]
+pub fn part1(input: &D19Input) -> usize {
+    count_matches(&input.rules, &input.messages)
+}
+
+#[aoc(day19, part2)]
+pub fn part2(input: &D19Input) -> usize {
+    let replacements = "8: 42 | 42 8\n11: 42 31 | 42 11 31";
+    let mut rules = input.rules.clone();
+    rules.extend(parse_rules(replacements, replacements.lines()).unwrap());
+    count_matches(&rules, &input.messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn p1_t1() {
+        let input = parse_input(SAMPLE_1);
+        assert_eq!(part1(&input), 2);
+    }
+
+    #[test]
+    fn p2_t1() {
+        let input = parse_input(SAMPLE_2);
+        assert_eq!(part1(&input), 3);
+        assert_eq!(part2(&input), 12);
+    }
+
+    #[test]
+    fn left_recursion() {
+        // 8 is one or more a, so 0 is two or more
+        let input = parse_input("0: 8 1\n8: 8 1 | 1\n1: \"a\"\n\naa\naaa\na\nab");
+        assert_eq!(2, part1(&input));
+        // through another rule: 0 is one or more a
+        let input = parse_input("0: 2 1 | 1\n2: 0\n1: \"a\"\n\na\naa\naaa\nb");
+        assert_eq!(3, part1(&input));
+        // a cycle that never consumes anything adds nothing: 0 is a single a
+        let input = parse_input("0: 2 | 1\n2: 0\n1: \"a\"\n\na\naa");
+        assert_eq!(1, part1(&input));
+        let input = parse_input("0: 0\n\na");
+        assert_eq!(0, part1(&input));
+        // the part 2 rules with 8 written left recursive
+        let mut input = parse_input(SAMPLE_2);
+        let replacements = "8: 8 42 | 42\n11: 42 31 | 42 11 31";
+        input
+            .rules
+            .extend(parse_rules(replacements, replacements.lines()).unwrap());
+        assert_eq!(12, part1(&input));
+    }
+
+    const SAMPLE_1: &str = r#"0: 4 1 5
+1: 2 3 | 3 2
+2: 4 4 | 5 5
+3: 4 5 | 5 4
+4: "a"
+5: "b"
+
+ababbb
+bababa
+abbbab
+aaabbb
+aaaabbb"#;
+
+    const SAMPLE_2: &str = r#"42: 9 14 | 10 1
+9: 14 27 | 1 26
+10: 23 14 | 28 1
+1: "a"
+11: 42 31
+5: 1 14 | 15 1
+19: 14 1 | 14 14
+12: 24 14 | 19 1
+16: 15 1 | 14 14
+31: 14 17 | 1 13
+6: 14 14 | 1 14
+2: 1 24 | 14 4
+0: 8 11
+13: 14 3 | 1 12
+15: 1 | 14
+17: 14 2 | 1 7
+23: 25 1 | 22 14
+28: 16 1
+4: 1 1
+20: 14 14 | 1 15
+3: 5 14 | 16 1
+27: 1 6 | 14 18
+14: "b"
+21: 14 1 | 1 14
+25: 1 1 | 1 14
+22: 14 14
+8: 42
+26: 14 22 | 1 20
+18: 15 15
+7: 14 5 | 1 21
+24: 14 1
+
+abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa
+bbabbbbaabaabba
+babbbbaabbbbbabbbbbbaabaaabaaa
+aaabbbbbbaaaabaababaabababbabaaabbababababaaa
+bbbbbbbaaaabbbbaaabbabaaa
+bbbababbbbaaaaaaaabbababaaababaabab
+ababaaaaaabaaab
+ababaaaaabbbaba
+baabbaaaabbaaaababbaababb
+abbbbabbbbaaaababbbbbbaaaababb
+aaaaabbaabaaaaababaa
+aaaabbaaaabbaaa
+aaaabbaabbaaaaaaabbbabbbaaabbaabaaa
+babaaabbbaaabaababbaabababaaab
+aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba"#;
+}
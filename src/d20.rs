@@ -1,19 +1,384 @@
+use std::collections::{HashMap, HashSet};
 
+use crate::error::ParseError;
+use crate::grid::Grid;
+
+const SEA_MONSTER: &str = concat!(
+    "                  # \n",
+    "#    ##    ##    ###\n",
+    " #  #  #  #  #  #   ",
+);
+
+#[derive(Debug, Clone)]
 pub struct Tile {
+    pub id: u64,
+    pub image: Grid<bool>,
+}
+
+impl Tile {
+    // top, right, bottom and left, read left to right and top to bottom
+    fn edges(&self) -> [Vec<bool>; 4] {
+        let size = self.image.width();
+        [
+            self.image.row(0),
+            self.image.column(size - 1),
+            self.image.row(size - 1),
+            self.image.column(0),
+        ]
+    }
+}
+
+// an edge and its reverse are the same edge, seen from a flipped tile
+fn signature(edge: &[bool]) -> Vec<bool> {
+    let reversed: Vec<bool> = edge.iter().rev().copied().collect();
+    reversed.min(edge.to_vec())
+}
+
+// the tiles with each edge signature, once even if two edges of a tile have it
+fn edge_index(tiles: &[Tile]) -> HashMap<Vec<bool>, Vec<usize>> {
+    let mut index: HashMap<Vec<bool>, Vec<usize>> = HashMap::new();
+    for (i, tile) in tiles.iter().enumerate() {
+        for edge in tile.edges().iter() {
+            let with = index.entry(signature(edge)).or_default();
+            if with.last() != Some(&i) {
+                with.push(i);
+            }
+        }
+    }
+    index
+}
+
+// edges that no other tile shares are on the border of the image
+fn is_outer(index: &HashMap<Vec<bool>, Vec<usize>>, edge: &[bool]) -> bool {
+    index[&signature(edge)].len() == 1
+}
 
+fn corners(tiles: &[Tile], index: &HashMap<Vec<bool>, Vec<usize>>) -> Vec<usize> {
+    (0..tiles.len())
+        .filter(|i| {
+            let edges = tiles[*i].edges();
+            edges.iter().filter(|edge| is_outer(index, edge)).count() == 2
+        })
+        .collect()
+}
+
+// the tiles in their place and orientation, row by row, or why they don't fit together
+fn assemble(tiles: &[Tile]) -> Result<Vec<Vec<Tile>>, String> {
+    let side = (tiles.len() as f64).sqrt() as usize;
+    let index = edge_index(tiles);
+    // a single tile is its own corner, with every edge outer
+    let corner = match tiles.len() {
+        1 => 0,
+        _ => *corners(tiles, &index).first().ok_or("no corner tile")?,
+    };
+
+    // any corner can go top left, turned so its outer edges face up and left
+    let first = tiles[corner]
+        .image
+        .orientations()
+        .into_iter()
+        .map(|image| Tile {
+            id: tiles[corner].id,
+            image,
+        })
+        .find(|tile| {
+            let [top, _, _, left] = tile.edges();
+            is_outer(&index, &top) && is_outer(&index, &left)
+        })
+        .ok_or_else(|| format!("tile {} can't go in a corner", tiles[corner].id))?;
+
+    let mut used = HashSet::new();
+    used.insert(corner);
+    let mut rows: Vec<Vec<Tile>> = vec![vec![first]];
+
+    for y in 0..side {
+        for x in 0..side {
+            if x == 0 && y == 0 {
+                continue;
+            }
+            // the edge to match, with the side of the new tile it has to be on
+            let (edge, side_index) = if x == 0 {
+                (rows[y - 1][0].edges()[2].clone(), 0)
+            } else {
+                (rows[y][x - 1].edges()[1].clone(), 3)
+            };
+
+            let next = index[&signature(&edge)]
+                .iter()
+                .find(|i| !used.contains(*i))
+                .ok_or_else(|| format!("no tile fits at ({}, {})", x, y))?;
+            let tile = tiles[*next]
+                .image
+                .orientations()
+                .into_iter()
+                .map(|image| Tile {
+                    id: tiles[*next].id,
+                    image,
+                })
+                .find(|tile| tile.edges()[side_index] == edge)
+                .ok_or_else(|| format!("tile {} doesn't fit", tiles[*next].id))?;
+
+            used.insert(*next);
+            if x == 0 {
+                rows.push(vec![tile]);
+            } else {
+                rows[y].push(tile);
+            }
+        }
+    }
+    Ok(rows)
+}
+
+// the assembled tiles without their borders
+fn image(tiles: &[Tile]) -> Result<Grid<bool>, String> {
+    let rows = assemble(tiles)?;
+    let inner = tiles[0].image.width() - 2;
+    let width = rows.len() * inner;
+
+    let mut cells = Vec::with_capacity(width * width);
+    for row in &rows {
+        let cropped: Vec<Grid<bool>> = row
+            .iter()
+            .map(|tile| tile.image.crop(1, 1, inner, inner))
+            .collect();
+        for y in 0..inner {
+            for grid in &cropped {
+                cells.extend(grid.row(y));
+            }
+        }
+    }
+    Ok(Grid::from_cells(width, cells))
 }
 
 #[aoc_generator(day20)]
 pub fn parse_input(input: &str) -> Vec<Tile> {
-    Vec::new()
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<Vec<Tile>, ParseError> {
+    let tiles = input
+        .split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .map(|block| {
+            let (header, image) = block.split_at(block.find('\n').unwrap_or(block.len()));
+            let id = header
+                .strip_prefix("Tile ")
+                .and_then(|s| s.strip_suffix(':'))
+                .and_then(|s| s.parse().ok())
+                .ok_or_else(|| ParseError::at(20, input, header, "expected `Tile <id>:`"))?;
+
+            let image = Grid::parse_in(20, input, image.trim_start_matches('\n'), |c| match c {
+                '#' => Some(true),
+                '.' => Some(false),
+                _ => None,
+            })?;
+            if image.width() < 3 || image.width() != image.height() {
+                return Err(ParseError::at(20, input, header, "tile is not square"));
+            }
+            Ok(Tile { id, image })
+        })
+        .collect::<Result<Vec<Tile>, ParseError>>()?;
+
+    let side = (tiles.len() as f64).sqrt() as usize;
+    if tiles.is_empty() || side * side != tiles.len() {
+        return Err(ParseError::input(20, "the tiles don't make a square"));
+    }
+    let size = tiles[0].image.width();
+    if let Some(tile) = tiles.iter().find(|tile| tile.image.width() != size) {
+        let message = format!("tile {} is not {} by {}", tile.id, size, size);
+        return Err(ParseError::input(20, message));
+    }
+    Ok(tiles)
 }
 
 #[aoc(day20, part1)]
-pub fn part1(tiles: &[Tile]) -> usize {
-    0
+pub fn part1(tiles: &[Tile]) -> u64 {
+    // the four corners of a single tile image are all the same tile
+    if tiles.len() == 1 {
+        return tiles[0].id.pow(4);
+    }
+    let index = edge_index(tiles);
+    let corners = corners(tiles, &index);
+    if corners.len() != 4 {
+        panic!("expected 4 corner tiles, found {}", corners.len());
+    }
+    corners.iter().map(|i| tiles[*i].id).product()
 }
 
+// the # that are not part of a sea monster, looking in the one orientation that has them, or
+// all of them when there are no sea monsters
 #[aoc(day20, part2)]
 pub fn part2(tiles: &[Tile]) -> usize {
-    0
+    let monster = Grid::parse(20, SEA_MONSTER, |c| Some(c == '#')).unwrap();
+    let image = image(tiles).unwrap_or_else(|e| panic!("{}", e));
+    let rough = image.iter().filter(|(_pos, cell)| **cell).count();
+
+    for image in image.orientations() {
+        let found = image.find_pattern(&monster, |m, cell| !*m || *cell);
+        if found.is_empty() {
+            continue;
+        }
+
+        let mut covered = HashSet::new();
+        for (x, y) in found {
+            for ((mx, my), m) in monster.iter() {
+                if *m {
+                    covered.insert((x + mx, y + my));
+                }
+            }
+        }
+        return rough - covered.len();
+    }
+    rough
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn p1_t1() {
+        assert_eq!(part1(&parse_input(SAMPLE_1)), 20899048083289);
+    }
+
+    #[test]
+    fn p2_t1() {
+        assert_eq!(part2(&parse_input(SAMPLE_1)), 273);
+    }
+
+    #[test]
+    fn single_tile() {
+        let tiles = parse_input("Tile 7:\n#..#\n.##.\n.#..\n#..#");
+        assert_eq!(2401, part1(&tiles));
+        // the inside of the tile, without sea monsters
+        assert_eq!(3, part2(&tiles));
+    }
+
+    #[test]
+    fn no_fit() {
+        // the middle tile with edges that match no other tile, and corner 1171 first
+        let mut tiles = parse_input(SAMPLE_1);
+        tiles.sort_by_key(|tile| tile.id);
+        let middle = tiles.iter_mut().find(|tile| tile.id == 1427).unwrap();
+        middle.image = Grid::from_cells(10, vec![false; 100]);
+        assert_eq!(
+            Err("no tile fits at (1, 1)".to_string()),
+            assemble(&tiles).map(|_| ())
+        );
+
+        // the tiles next to the middle one now have two outer edges on opposite sides
+        tiles.sort_by_key(|tile| tile.id != 2311);
+        assert_eq!(
+            Err("tile 2311 can't go in a corner".to_string()),
+            assemble(&tiles).map(|_| ())
+        );
+    }
+
+    const SAMPLE_1: &str = "Tile 2311:
+..##.#..#.
+##..#.....
+#...##..#.
+####.#...#
+##.##.###.
+##...#.###
+.#.#.#..##
+..#....#..
+###...#.#.
+..###..###
+
+Tile 1951:
+#.##...##.
+#.####...#
+.....#..##
+#...######
+.##.#....#
+.###.#####
+###.##.##.
+.###....#.
+..#.#..#.#
+#...##.#..
+
+Tile 1171:
+####...##.
+#..##.#..#
+##.#..#.#.
+.###.####.
+..###.####
+.##....##.
+.#...####.
+#.##.####.
+####..#...
+.....##...
+
+Tile 1427:
+###.##.#..
+.#..#.##..
+.#.##.#..#
+#.#.#.##.#
+....#...##
+...##..##.
+...#.#####
+.#.####.#.
+..#..###.#
+..##.#..#.
+
+Tile 1489:
+##.#.#....
+..##...#..
+.##..##...
+..#...#...
+#####...#.
+#..#.#.#.#
+...#.#.#..
+##.#...##.
+..##.##.##
+###.##.#..
+
+Tile 2473:
+#....####.
+#..#.##...
+#.##..#...
+######.#.#
+.#...#.#.#
+.#########
+.###.#..#.
+########.#
+##...##.#.
+..###.#.#.
+
+Tile 2971:
+..#.#....#
+#...###...
+#.#.###...
+##.##..#..
+.#####..##
+.#..####.#
+#..#.#..#.
+..####.###
+..#.#.###.
+...#.#.#.#
+
+Tile 2729:
+...#.#.#.#
+####.#....
+..#.#.....
+....#..#.#
+.##..##.#.
+.#.####...
+####.#.#..
+##.####...
+##..#.##..
+#.##...##.
+
+Tile 3079:
+#.#.#####.
+.#..######
+..#.......
+######....
+####.#..#.
+.#...#.##.
+#.#####.##
+..#.###...
+..#.......
+..#.###...";
 }
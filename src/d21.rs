@@ -2,6 +2,8 @@ use std::collections::{HashMap, HashSet};
 
 use itertools::Itertools;
 
+use crate::error::ParseError;
+
 #[derive(Debug, Clone)]
 pub struct Food {
     ingredients: HashSet<String>,
@@ -16,7 +18,7 @@ impl Food {
         }
     }
 
-    fn from_str(line: &str) -> Self {
+    fn from_str(line: &str) -> Option<Self> {
         let mut s = line.split(" (contains ");
         let mut food = Food::new();
 
@@ -25,12 +27,12 @@ impl Food {
             food.ingredients.insert(i.to_string());
         }
 
-        let allergens_str = s.next().unwrap().replace(')', "");
+        let allergens_str = s.next()?.strip_suffix(')')?;
         for allergen in allergens_str.split(", ") {
             food.allergens.insert(allergen.to_string());
         }
 
-        food
+        Some(food)
     }
 }
 fn solve(foods: &[Food]) -> (usize, String) {
@@ -124,7 +126,7 @@ fn solve(foods: &[Food]) -> (usize, String) {
         }
     }
 
-    let mut sorted_keys = solved.keys().into_iter().cloned().collect::<Vec<String>>();
+    let mut sorted_keys = solved.keys().cloned().collect::<Vec<String>>();
     sorted_keys.sort();
 
     let mut canonical = String::new();
@@ -137,7 +139,23 @@ fn solve(foods: &[Food]) -> (usize, String) {
 
 #[aoc_generator(day21)]
 pub fn parse_input(input: &str) -> Vec<Food> {
-    input.lines().map(Food::from_str).collect()
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<Vec<Food>, ParseError> {
+    input
+        .lines()
+        .map(|line| {
+            Food::from_str(line).ok_or_else(|| {
+                ParseError::at(
+                    21,
+                    input,
+                    line,
+                    "expected `<ingredients> (contains <allergens>)`",
+                )
+            })
+        })
+        .collect()
 }
 
 #[aoc(day21, part1)]
@@ -1,5 +1,7 @@
 use std::collections::HashSet;
 
+use crate::error::ParseError;
+
 pub struct Decks {
     p1_deck: Vec<u8>,
     p2_deck: Vec<u8>,
@@ -7,28 +9,41 @@ pub struct Decks {
 
 #[aoc_generator(day22)]
 pub fn parse_input(input: &str) -> Decks {
-    let mut players = input.split("\n\n");
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
 
-    let player1 = players.next().unwrap();
-    let mut p1 = player1
-        .lines()
-        .skip(1)
-        .map(|l| l.parse::<u8>().unwrap())
-        .collect::<Vec<u8>>();
-    p1.reverse();
-
-    let player2 = players.next().unwrap();
-    let mut p2 = player2
-        .lines()
-        .skip(1)
-        .map(|l| l.parse::<u8>().unwrap())
-        .collect::<Vec<u8>>();
-    p2.reverse();
-
-    Decks {
-        p1_deck: p1,
-        p2_deck: p2,
+// a player's deck, top card last
+fn parse_deck(input: &str, player: Option<&str>, header: &str) -> Result<Vec<u8>, ParseError> {
+    let player =
+        player.ok_or_else(|| ParseError::input(22, format!("missing `{}` section", header)))?;
+    let mut lines = player.lines();
+    let first = lines.next().unwrap_or(player);
+    if first != header {
+        return Err(ParseError::at(
+            22,
+            input,
+            first,
+            format!("expected `{}`", header),
+        ));
     }
+
+    let mut deck = lines
+        .map(|l| {
+            l.parse::<u8>()
+                .map_err(|_| ParseError::at(22, input, l, "expected a card number"))
+        })
+        .collect::<Result<Vec<u8>, ParseError>>()?;
+    deck.reverse();
+    Ok(deck)
+}
+
+pub fn try_parse_input(input: &str) -> Result<Decks, ParseError> {
+    let mut players = input.split("\n\n");
+
+    Ok(Decks {
+        p1_deck: parse_deck(input, players.next(), "Player 1:")?,
+        p2_deck: parse_deck(input, players.next(), "Player 2:")?,
+    })
 }
 
 fn combat(player1_deck: &mut Vec<u8>, player2_deck: &mut Vec<u8>) -> usize {
@@ -46,9 +61,9 @@ fn combat(player1_deck: &mut Vec<u8>, player2_deck: &mut Vec<u8>) -> usize {
     }
 
     if !player1_deck.is_empty() {
-        calculate_score(&player1_deck)
+        calculate_score(player1_deck)
     } else {
-        calculate_score(&player2_deck)
+        calculate_score(player2_deck)
     }
 }
 
@@ -1,18 +1,40 @@
 use itertools::Itertools;
 
+use crate::error::{char_at, ParseError};
+
 const STEPS: usize = 100;
 
 fn parse_input(data: &str) -> Vec<usize> {
-    data.chars()
-        .map(|c| c.to_digit(10).unwrap() as usize)
-        .collect()
+    try_parse_input(data).unwrap_or_else(|e| panic!("{}", e))
+}
+
+// the cups must be labelled 1 to n, in any order, with at least 4 of them
+pub fn try_parse_input(data: &str) -> Result<Vec<usize>, ParseError> {
+    let data = data.trim_end();
+    let mut cups = Vec::new();
+    for (i, c) in data.char_indices() {
+        let cup = c.to_digit(10).map(|d| d as usize);
+        match cup {
+            Some(cup) if cup >= 1 && !cups.contains(&cup) => cups.push(cup),
+            _ => {
+                let message = "expected an unused label 1-9";
+                return Err(ParseError::at(23, data, char_at(data, i), message));
+            }
+        }
+    }
+
+    if cups.len() < 4 || cups.iter().any(|cup| *cup > cups.len()) {
+        let message = "expected the labels 1 to n, n >= 4";
+        return Err(ParseError::at(23, data, data, message));
+    }
+    Ok(cups)
 }
 
 fn step(cups: &mut Vec<usize>) {
     //println!("=====");
     //println!("cups: {:?}", cups);
 
-    let current = *cups.get(0).unwrap();
+    let current = *cups.first().unwrap();
     //println!("current: {}", current);
 
     let r1 = *cups.get(1).unwrap();
@@ -49,10 +71,10 @@ pub fn part1(input: &str) -> String {
     for _i in 1..=STEPS {
         step(&mut cups);
     }
-    println!("START: {:?}", cups);
+    //println!("START: {:?}", cups);
     let one_pos = cups.iter().position(|&x| x == 1).unwrap();
     cups.rotate_left(one_pos);
-    println!("END: {:?}", cups);
+    //println!("END: {:?}", cups);
 
     let mut it = cups.iter();
     it.next();
@@ -3,10 +3,7 @@ use std::fmt::{Debug, Formatter};
 const LEN: usize = 1_000_000;
 const STEPS: usize = 10_000_000;
 fn parse_input(seed_str: &str) -> Vec<usize> {
-    seed_str
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as usize)
-        .collect()
+    crate::d23_1::try_parse_input(seed_str).unwrap_or_else(|e| panic!("{}", e))
 }
 
 struct Cups {
@@ -1,7 +1,12 @@
 use std::collections::{HashMap, HashSet};
-use std::hash::Hash;
 use Dir::{East, NorthEast, NorthWest, SouthEast, SouthWest, West};
 
+use crate::automaton::{Automaton, Hex, HEX_DIRECTIONS};
+use crate::error::{char_at, ParseError};
+use crate::grid::Grid;
+use crate::render::{self, Frame};
+
+// in the same order as HEX_DIRECTIONS
 #[derive(Debug, Copy, Clone)]
 pub enum Dir {
     East,
@@ -13,242 +18,202 @@ pub enum Dir {
 }
 
 impl Dir {
-    fn from_str(s: &str) -> Self {
+    fn from_str(s: &str) -> Option<Self> {
         match s {
-            "e" => East,
-            "se" => SouthEast,
-            "sw" => SouthWest,
-            "w" => West,
-            "nw" => NorthWest,
-            "ne" => NorthEast,
-            _ => panic!("unknown [{}]", s),
+            "e" => Some(East),
+            "se" => Some(SouthEast),
+            "sw" => Some(SouthWest),
+            "w" => Some(West),
+            "nw" => Some(NorthWest),
+            "ne" => Some(NorthEast),
+            _ => None,
         }
     }
 }
 
-#[derive(Debug, Hash, Eq, PartialEq, Clone)]
-struct HexCoord {
-    x: i32,
-    y: i32,
-    z: i32,
-}
-
-impl HexCoord {
-    fn origin() -> Self {
-        HexCoord { x: 0, y: 0, z: 0 }
-    }
-
-    fn get_neighbor(&self, dir: Dir) -> HexCoord {
-        match dir {
-            East => HexCoord {
-                x: self.x + 1,
-                y: self.y - 1,
-                z: self.z,
-            },
-            SouthEast => HexCoord {
-                x: self.x,
-                y: self.y - 1,
-                z: self.z + 1,
-            },
-            SouthWest => HexCoord {
-                x: self.x - 1,
-                y: self.y,
-                z: self.z + 1,
-            },
-            West => HexCoord {
-                x: self.x - 1,
-                y: self.y + 1,
-                z: self.z,
-            },
-            NorthWest => HexCoord {
-                x: self.x,
-                y: self.y + 1,
-                z: self.z - 1,
-            },
-            NorthEast => HexCoord {
-                x: self.x + 1,
-                y: self.y,
-                z: self.z - 1,
-            },
-        }
+// the tile a line of directions leads to from the reference tile, in axial coordinates
+fn tile_at(directions: &[Dir]) -> (i32, i32) {
+    let mut tile = (0, 0);
+    for dir in directions {
+        let (dq, dr) = HEX_DIRECTIONS[*dir as usize];
+        tile = (tile.0 + dq, tile.1 + dr);
     }
+    tile
 }
 
-impl Coord for HexCoord {
-    fn get_adjacent(&self) -> Vec<Self>
-    where
-        Self: Sized,
-    {
-        vec![
-            self.get_neighbor(East),
-            self.get_neighbor(SouthEast),
-            self.get_neighbor(SouthWest),
-            self.get_neighbor(West),
-            self.get_neighbor(NorthEast),
-            self.get_neighbor(NorthWest),
-        ]
-    }
-}
-
-fn seed_board_for_active(tile_directions: &[Vec<Dir>]) -> Vec<HexCoord> {
-    let mut tile_state: HashMap<HexCoord, bool> = HashMap::new();
+// the black tiles, in axial coordinates
+fn seed_board_for_active(tile_directions: &[Vec<Dir>]) -> Vec<(i32, i32)> {
+    let mut tile_state: HashMap<(i32, i32), bool> = HashMap::new();
 
     for directions in tile_directions {
-        let mut tile = HexCoord::origin();
-
-        for dir in directions {
-            tile = tile.get_neighbor(*dir);
-        }
-
-        if tile_state.contains_key(&tile) {
-            let state = tile_state.get_mut(&tile).unwrap();
-            *state ^= true;
-        } else {
-            tile_state.insert(tile, true);
-        }
-
+        *tile_state.entry(tile_at(directions)).or_insert(false) ^= true;
     }
 
     tile_state
         .iter()
         .filter(|kv| *kv.1)
-        .map(|kv| kv.0)
-        .cloned()
-        .collect::<Vec<HexCoord>>()
+        .map(|kv| *kv.0)
+        .collect::<Vec<(i32, i32)>>()
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum State {
-    Active,
-    Inactive,
+#[aoc_generator(day24)]
+pub fn parse_input(input: &str) -> Vec<Vec<Dir>> {
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
 }
 
-trait Coord {
-    fn get_adjacent(&self) -> Vec<Self>
-    where
-        Self: Sized;
+pub fn try_parse_input(input: &str) -> Result<Vec<Vec<Dir>>, ParseError> {
+    input
+        .lines()
+        .map(|line| {
+            let mut dirs = Vec::new();
+            let mut start = 0;
+            // every direction ends in e or w
+            for (i, c) in line.char_indices() {
+                if c == 'e' || c == 'w' {
+                    let s = &line[start..=i];
+                    let dir = Dir::from_str(s)
+                        .ok_or_else(|| ParseError::at(24, input, s, "unknown direction"))?;
+                    dirs.push(dir);
+                    start = i + 1;
+                } else if c != 'n' && c != 's' || i > start {
+                    return Err(ParseError::at(
+                        24,
+                        input,
+                        char_at(line, i),
+                        "unknown direction",
+                    ));
+                }
+            }
+            if start < line.len() {
+                let rest = &line[start..];
+                return Err(ParseError::at(24, input, rest, "incomplete direction"));
+            }
+            Ok(dirs)
+        })
+        .collect()
 }
 
-struct Conway<T>
-where
-    T: Hash,
-    T: Eq,
-    T: Coord,
-    T: Clone,
-{
-    coords: HashMap<T, State>,
+#[aoc(day24, part1)]
+pub fn part1(tile_directions: &[Vec<Dir>]) -> usize {
+    seed_board_for_active(tile_directions).len()
 }
 
-impl<T> Conway<T>
+// A black tile with zero or more than 2 black neighbours is flipped to white, a white tile
+// with exactly 2 is flipped to black. f sees the floor before the first day and after each.
+fn exhibit<F>(tile_directions: &[Vec<Dir>], f: F) -> usize
 where
-    T: Hash,
-    T: Eq,
-    T: Coord,
-    T: Clone,
+    F: FnMut(&Automaton<Hex>),
 {
-    fn new() -> Self {
-        Self {
-            coords: HashMap::new(),
-        }
-    }
+    let rule = "B2/S12".parse().unwrap();
+    let black = seed_board_for_active(tile_directions);
+    Automaton::new(Hex, rule, black).run_with(100, f)
+}
 
-    fn total_active_coords(&self) -> usize {
-        self.coords
-            .values()
-            .filter(|v| **v == State::Active)
-            .count()
+// the hexagon under a point, with pointy tops and the given radius, and how far the point is
+// from its centre: 0 at the centre and 0.5 halfway along an edge
+fn hex_at(x: f64, y: f64, radius: f64) -> ((i32, i32), f64) {
+    let q = (3_f64.sqrt() / 3.0 * x - y / 3.0) / radius;
+    let r = 2.0 / 3.0 * y / radius;
+    let s = -q - r;
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
     }
+    let distance = (q - rq).abs().max((r - rr).abs()).max((s + rq + rr).abs());
+    ((rq as i32, rr as i32), distance)
+}
 
-    fn get_coords_to_check(&self) -> HashSet<T> {
-        let mut to_check: HashSet<T> = HashSet::new();
-        for coord in self.coords.keys() {
-            to_check.insert(coord.clone());
-            for c in coord.get_adjacent() {
-                to_check.insert(c.clone());
+// Part 1 flips a tile per line, part 2 is a frame per day. The text uses doubled
+// coordinates, column 2q + r and row r, so tiles next to each other in a row are two
+// columns apart.
+pub fn frames(tile_directions: &[Vec<Dir>], part: u32) -> Vec<Frame> {
+    let mut floors: Vec<HashSet<(i32, i32)>> = Vec::new();
+    if part == 1 {
+        // one tile flipped per line
+        let mut black = HashSet::new();
+        floors.push(black.clone());
+        for directions in tile_directions {
+            let tile = tile_at(directions);
+            if !black.remove(&tile) {
+                black.insert(tile);
             }
+            floors.push(black.clone());
         }
-        to_check
+    } else {
+        exhibit(tile_directions, |floor| {
+            floors.push(floor.live().copied().collect())
+        });
     }
 
-    fn get_coord_state(&self, coord: &T) -> State {
-        if let Some(state) = self.coords.get(&coord) {
-            if *state == State::Active {
-                return State::Active;
-            }
-        }
-        State::Inactive
+    // column and row bounds over every frame, around the reference tile
+    let (mut min, mut max) = ((0, 0), (0, 0));
+    for (q, r) in floors.iter().flatten() {
+        min = (min.0.min(2 * q + r), min.1.min(*r));
+        max = (max.0.max(2 * q + r), max.1.max(*r));
     }
 
-    fn count_active_neighbors(&self, origin: &T) -> usize {
-        let mut count: usize = 0;
-        for coord in origin.get_adjacent() {
-            if self.get_coord_state(&coord) == State::Active {
-                count += 1;
+    let radius = 6.0;
+    let step = (3_f64.sqrt() / 2.0 * radius, 1.5 * radius);
+    let width = ((max.0 - min.0 + 2) as f64 * step.0).ceil() as usize;
+    let height = ((max.1 - min.1 + 2) as f64 * step.1).ceil() as usize;
+    let origin = ((1 - min.0) as f64 * step.0, (1 - min.1) as f64 * step.1);
+
+    // the same for every frame: the floor with every tile white, and the pixels of each tile
+    let mut white = Grid::new(width, height, render::BACKGROUND);
+    let mut pixels: HashMap<(i32, i32), Vec<(usize, usize)>> = HashMap::new();
+    for y in 0..height {
+        for x in 0..width {
+            let point = (x as f64 + 0.5 - origin.0, y as f64 + 0.5 - origin.1);
+            let ((q, r), distance) = hex_at(point.0, point.1, radius);
+            let (col, row) = (2 * q + r, r);
+            if col < min.0 || col > max.0 || row < min.1 || row > max.1 {
+                continue;
             }
-        }
-        count
-    }
-
-    fn step(&mut self) {
-        let mut new_coords: HashMap<T, State> = HashMap::new();
-
-        for coord in self.get_coords_to_check() {
-            let state = self.get_coord_state(&coord);
-            let active_neighbor_count = self.count_active_neighbors(&coord);
-            match state {
-                State::Active => {
-                    if active_neighbor_count == 0 || active_neighbor_count > 2 {
-                        new_coords.insert(coord, State::Inactive);
-                    } else {
-                        new_coords.insert(coord, State::Active);
-                    }
-                }
-                State::Inactive => {
-                    if active_neighbor_count == 2 {
-                        new_coords.insert(coord, State::Active);
-                    }
-                }
+            if distance > 0.4 {
+                white[(x, y)] = [110, 110, 120];
+            } else {
+                white[(x, y)] = [235, 235, 225];
+                pixels.entry((q, r)).or_default().push((x, y));
             }
         }
-        self.coords = new_coords;
     }
-}
 
-#[aoc_generator(day24)]
-pub fn parse_input(input: &str) -> Vec<Vec<Dir>> {
-    input
-        .lines()
-        .map(|line| {
-            let modified = line.replace("e", "e,").replace("w", "w,");
+    floors
+        .iter()
+        .map(|black| {
+            let text: Vec<String> = (min.1..=max.1)
+                .map(|r| {
+                    (min.0..=max.0)
+                        .map(|col| match (col - r).rem_euclid(2) {
+                            0 if black.contains(&((col - r) / 2, r)) => '#',
+                            0 => '.',
+                            _ => ' ',
+                        })
+                        .collect::<String>()
+                        .trim_end()
+                        .to_string()
+                })
+                .collect();
+
+            let mut image = white.clone();
+            for position in black.iter().filter_map(|tile| pixels.get(tile)).flatten() {
+                image[*position] = [30, 30, 30];
+            }
 
-            modified[0..modified.len() - 1]
-                .split(',')
-                .map(Dir::from_str)
-                .collect::<Vec<Dir>>()
+            Frame {
+                text: text.join("\n"),
+                image,
+            }
         })
         .collect()
 }
 
-#[aoc(day24, part1)]
-pub fn part1(tile_directions: &[Vec<Dir>]) -> usize {
-    seed_board_for_active(&tile_directions).iter().count()
-}
-
 #[aoc(day24, part2)]
 pub fn part2(tile_directions: &[Vec<Dir>]) -> usize {
-    let active_coords = seed_board_for_active(tile_directions);
-
-    let mut conway: Conway<HexCoord> = Conway::new();
-    for coord in active_coords {
-        conway.coords.insert(coord, State::Active);
-    }
-
-    for _i in 0..100 {
-        conway.step();
-        //println!("day {}: {} active tiles", i + 1, conway.total_active_coords());
-    }
-
-    conway.total_active_coords()
+    exhibit(tile_directions, |_| {})
 }
 
 #[cfg(test)]
@@ -1,3 +1,5 @@
+use crate::error::{parse_numbers, ParseError};
+
 type PubKey = usize;
 
 fn transform_subject(num: usize, loop_size: usize) -> usize {
@@ -24,10 +26,27 @@ fn determine_loop_size(key: PubKey) -> usize {
 
 #[aoc_generator(day25)]
 pub fn parse_input(input: &str) -> Vec<PubKey> {
-    input
+    try_parse_input(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_input(input: &str) -> Result<Vec<PubKey>, ParseError> {
+    let keys: Vec<PubKey> = parse_numbers(25, input)?;
+    // anything else would never be reached by determine_loop_size
+    if let Some(line) = input
         .lines()
-        .map(|l| l.parse::<PubKey>().unwrap())
-        .collect()
+        .zip(&keys)
+        .find(|(_, key)| **key == 0 || **key >= 20201227)
+        .map(|(line, _)| line)
+    {
+        return Err(ParseError::at(25, input, line, "key out of range"));
+    }
+    if keys.len() != 2 {
+        return Err(ParseError::input(
+            25,
+            "expected the card and door public keys",
+        ));
+    }
+    Ok(keys)
 }
 
 #[aoc(day25, part1)]
@@ -58,6 +77,6 @@ mod tests {
     fn p1_t1() {
         assert_eq!(8, determine_loop_size(5764801));
         assert_eq!(11, determine_loop_size(17807724));
-        assert_eq!(14897079, part1(&vec![5764801, 17807724]));
+        assert_eq!(14897079, part1(&[5764801, 17807724]));
     }
 }
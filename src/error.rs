@@ -0,0 +1,135 @@
+use std::fmt::{Display, Formatter};
+
+// Errors from the fallible generators (`try_parse_input`) of every day.
+//
+// Positions are 1-based and counted in characters. The generators work on slices of the
+// original input, so an error is built from the offending slice and its position is worked
+// out from where that slice starts.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u32,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub message: String,
+}
+
+impl ParseError {
+    // `text` must be a slice of `input`
+    pub fn at(day: u32, input: &str, text: &str, message: impl Into<String>) -> Self {
+        let (line, column) = position(input, text);
+        ParseError {
+            day,
+            line,
+            column,
+            text: text.to_string(),
+            message: message.into(),
+        }
+    }
+
+    // for problems with the input as a whole, such as a missing section
+    pub fn input(day: u32, message: impl Into<String>) -> Self {
+        ParseError {
+            day,
+            line: 0,
+            column: 0,
+            text: String::new(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            return write!(f, "day {}: {}", self.day, self.message);
+        }
+        write!(
+            f,
+            "day {}, line {}, column {}: {} `{}`",
+            self.day, self.line, self.column, self.message, self.text
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// the line and column where `text`, a slice of `input`, starts
+pub fn position(input: &str, text: &str) -> (usize, usize) {
+    let offset = (text.as_ptr() as usize)
+        .checked_sub(input.as_ptr() as usize)
+        .filter(|offset| offset + text.len() <= input.len())
+        .expect("error text is not part of the input");
+
+    let before = &input[..offset];
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+// the character at byte index i of s, as a slice of s
+pub fn char_at(s: &str, i: usize) -> &str {
+    let len = s[i..].chars().next().map_or(0, char::len_utf8);
+    &s[i..i + len]
+}
+
+// the usual case of one number per line
+pub fn parse_numbers<T: std::str::FromStr>(day: u32, input: &str) -> Result<Vec<T>, ParseError> {
+    input
+        .lines()
+        .map(|line| {
+            line.trim()
+                .parse::<T>()
+                .map_err(|_| ParseError::at(day, input, line, "expected a number"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn position() {
+        let input = "abc\ndéf\nghi";
+        let error = ParseError::at(3, input, &input[7..8], "bad");
+        assert_eq!((2, 3), (error.line, error.column));
+        assert_eq!("f", error.text);
+        assert_eq!("day 3, line 2, column 3: bad `f`", error.to_string());
+
+        let error = ParseError::at(3, input, &input[9..], "bad");
+        assert_eq!((3, 1), (error.line, error.column));
+    }
+
+    #[test]
+    fn generators() {
+        let error = d01::try_parse_input("1721\n979\n36x6").unwrap_err();
+        assert_eq!((1, 3, 1), (error.day, error.line, error.column));
+        assert_eq!("36x6", error.text);
+
+        let error = d08::try_parse_input("nop +0\nacc +1\nacc +1\nhcf +0").unwrap_err();
+        assert_eq!((4, 1), (error.line, error.column));
+        assert_eq!("hcf", error.text);
+
+        let error = d08::try_parse_input("nop +0\nacc +x").unwrap_err();
+        assert_eq!((2, 5), (error.line, error.column));
+
+        let error = d24::try_parse_input("esew\nnwwswee\nnwxe").unwrap_err();
+        assert_eq!((3, 3), (error.line, error.column));
+        assert_eq!("x", error.text);
+
+        let error = d16::try_parse_input("class: 1-3 or 5-7\n\nyour ticket:\n7,1,14").unwrap_err();
+        assert_eq!(ParseError::input(16, "missing nearby tickets"), error);
+
+        let error = d11::try_parse_input("L.L\nL#?").unwrap_err();
+        assert_eq!((2, 3), (error.line, error.column));
+
+        assert!(d04::try_parse_input("byr:1937 iyr:2017\nxyz:1").is_err());
+        assert!(d12::try_parse_input("F10\nR45").is_err());
+        assert!(d18::try_parse_input("1 + (2 * 3").is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::solution::{self, Answer};
+
+// Known answers for puzzle inputs, listed in a manifest file.
+//
+// Each line of a manifest is `<day> <part> <input file> <answer>`, with the input file
+// relative to the manifest, or `<day> <part> <answer>` for the input at <dir>/day<N>.txt.
+// Blank lines and lines starting with # are ignored. tests/fixtures/manifest.txt is turned
+// into one test per line by build.rs.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixture {
+    pub day: u32,
+    pub part: u32,
+    pub input: PathBuf,
+    pub expected: String,
+    // line in the manifest, for error messages
+    pub line: usize,
+}
+
+pub fn parse_manifest(text: &str, base: &Path, dir: &Path) -> Result<Vec<Fixture>, String> {
+    let mut fixtures = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (day, part, input, expected) = match fields[..] {
+            [day, part, input, expected] => (day, part, Some(input), expected),
+            [day, part, expected] => (day, part, None, expected),
+            _ => {
+                let message = "expected `<day> <part> [input file] <answer>`";
+                return Err(format!("line {}: {}", i + 1, message));
+            }
+        };
+        let day = day
+            .parse()
+            .map_err(|_| format!("line {}: invalid day {}", i + 1, day))?;
+        let part = part
+            .parse()
+            .map_err(|_| format!("line {}: invalid part {}", i + 1, part))?;
+
+        fixtures.push(Fixture {
+            day,
+            part,
+            input: match input {
+                Some(input) => base.join(input),
+                None => dir.join(format!("day{}.txt", day)),
+            },
+            expected: expected.to_string(),
+            line: i + 1,
+        });
+    }
+    Ok(fixtures)
+}
+
+pub fn load(manifest: &Path, dir: &Path) -> Result<Vec<Fixture>, String> {
+    let text = fs::read_to_string(manifest)
+        .map_err(|e| format!("can't read {}: {}", manifest.display(), e))?;
+    let base = manifest.parent().unwrap_or_else(|| Path::new(""));
+    parse_manifest(&text, base, dir)
+        .map_err(|message| format!("{}: {}", manifest.display(), message))
+}
+
+// the generators expect the input without its final newline, as cargo-aoc passes it
+pub fn read_input(path: &Path) -> Result<String, String> {
+    let input =
+        fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+    Ok(input.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+impl Fixture {
+    pub fn run(&self) -> Result<Answer, String> {
+        let input = read_input(&self.input)?;
+        solution::solve(self.day, self.part, &input).map_err(|e| e.to_string())
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        let actual = self.run()?.to_string();
+        if actual == self.expected {
+            Ok(())
+        } else {
+            Err(format!(
+                "day {} part {} ({}): expected {} got {}",
+                self.day,
+                self.part,
+                self.input.display(),
+                self.expected,
+                actual
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest() {
+        let text = "# comment\n\n1 2 sample.txt 241861950\n8 1 1528\n";
+        let fixtures = parse_manifest(text, Path::new("fixtures"), Path::new("input")).unwrap();
+        assert_eq!(
+            vec![
+                Fixture {
+                    day: 1,
+                    part: 2,
+                    input: PathBuf::from("fixtures/sample.txt"),
+                    expected: "241861950".to_string(),
+                    line: 3,
+                },
+                Fixture {
+                    day: 8,
+                    part: 1,
+                    input: PathBuf::from("input/day8.txt"),
+                    expected: "1528".to_string(),
+                    line: 4,
+                }
+            ],
+            fixtures
+        );
+
+        assert!(parse_manifest("1 x 514579", Path::new(""), Path::new("")).is_err());
+        assert!(parse_manifest("1 1", Path::new(""), Path::new("")).is_err());
+    }
+}
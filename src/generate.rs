@@ -0,0 +1,1304 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use crate::automaton::{Automaton, Bounded, Topology};
+use crate::d08::{Cpu, CpuState, Instruction, OpCode};
+use crate::grid::Grid;
+use crate::solution::{Answer, Error};
+
+// Random but valid puzzle inputs for every day, for benchmarks and stress tests.
+//
+// The same seed always gives the same input. `size` is roughly the number of lines or items
+// and is clamped to what each day can take. When the construction fixes an answer (a planted
+// pair for day 1, a staircase of field ranges for day 16, ...) it is returned with the input,
+// the simulation days only get an input.
+
+// splitmix64, good enough for test data and the same on every platform
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform in low..=high
+    pub fn range(&mut self, low: usize, high: usize) -> usize {
+        assert!(low <= high, "empty range {}..={}", low, high);
+        low + (self.next_u64() % (high - low + 1) as u64) as usize
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        self.range(0, n - 1)
+    }
+
+    pub fn percent(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.range(0, i));
+        }
+    }
+
+    // count different values from low..=high
+    pub fn sample(&mut self, low: usize, high: usize, count: usize) -> Vec<usize> {
+        let mut values: Vec<usize> = (low..=high).collect();
+        self.shuffle(&mut values);
+        values.truncate(count);
+        values
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generated {
+    pub input: String,
+    pub part1: Option<Answer>,
+    pub part2: Option<Answer>,
+}
+
+impl Generated {
+    fn known(input: String, part1: impl Into<Answer>, part2: impl Into<Answer>) -> Self {
+        Generated {
+            input,
+            part1: Some(part1.into()),
+            part2: Some(part2.into()),
+        }
+    }
+
+    fn unknown(input: String) -> Self {
+        Generated {
+            input,
+            part1: None,
+            part2: None,
+        }
+    }
+
+    pub fn answer(&self, part: u32) -> Option<&Answer> {
+        match part {
+            1 => self.part1.as_ref(),
+            2 => self.part2.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+pub fn generate(day: u32, seed: u64, size: usize) -> Result<Generated, Error> {
+    let rng = &mut Rng::new(seed);
+    let generated = match day {
+        1 => d01(rng, size.clamp(5, 200)),
+        2 => d02(rng, size.clamp(1, 1000)),
+        3 => d03(rng, size.clamp(2, 1000)),
+        4 => d04(rng, size.clamp(1, 1000)),
+        5 => d05(rng, size.clamp(3, 1000)),
+        6 => d06(rng, size.clamp(1, 1000)),
+        7 => d07(rng, size.clamp(6, 300)),
+        8 => d08(rng, size.clamp(10, 1000)),
+        9 => d09(rng, size.clamp(40, 1000)),
+        10 => d10(rng, size.clamp(1, 100)),
+        11 => d11(rng, size.clamp(3, 100)),
+        12 => d12(rng, size.clamp(1, 1000)),
+        13 => d13(rng, size.clamp(2, 9)),
+        14 => d14(rng, size.clamp(1, 200)),
+        15 => d15(rng, size.clamp(2, 7)),
+        16 => d16(rng, size.clamp(5, 300)),
+        17 => d17(rng, size.clamp(3, 10)),
+        18 => d18(rng, size.clamp(1, 500)),
+        19 => d19(rng, size.clamp(1, 500)),
+        20 => d20(rng, size.clamp(3, 8)),
+        21 => d21(rng, size.clamp(1, 100)),
+        22 => d22(rng, size.clamp(2, 25)),
+        23 => d23(rng),
+        24 => d24(rng, size.clamp(1, 1000)),
+        25 => d25(rng, size.clamp(1, 1000)),
+        _ => return Err(Error::UnknownDay(day)),
+    };
+    Ok(generated)
+}
+
+fn lines<T: Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn grid_text(
+    rng: &mut Rng,
+    width: usize,
+    height: usize,
+    chars: [char; 2],
+    percent: usize,
+) -> String {
+    let rows: Vec<String> = (0..height)
+        .map(|_| {
+            (0..width)
+                .map(|_| chars[rng.percent(percent) as usize])
+                .collect()
+        })
+        .collect();
+    rows.join("\n")
+}
+
+// one pair and one triple add up to 2020, every other number is too large to take part
+fn d01(rng: &mut Rng, count: usize) -> Generated {
+    loop {
+        let a = rng.range(1011, 2019);
+        let x = rng.range(200, 1009);
+        let y = rng.range(1011 - x, 1009);
+        let z = 2020 - x - y;
+        let mut entries = vec![a, 2020 - a, x, y, z];
+
+        // fillers that would make another pair or triple with the small numbers
+        let mut forbidden: HashSet<usize> = entries.iter().copied().collect();
+        for (i, s) in entries[1..].iter().enumerate() {
+            forbidden.insert(2020 - s);
+            forbidden.insert(2020_usize.saturating_sub(2 * s));
+            for t in &entries[i + 2..] {
+                forbidden.insert(2020_usize.saturating_sub(s + t));
+            }
+        }
+        while entries.len() < count {
+            let filler = rng.range(1011, 2019);
+            if forbidden.insert(filler) {
+                entries.push(filler);
+            }
+        }
+
+        let mut sorted = entries.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.len() != entries.len() {
+            continue;
+        }
+        let set: HashSet<usize> = sorted.iter().copied().collect();
+        let pairs = sorted
+            .iter()
+            .filter(|n| 2020 - **n > **n && set.contains(&(2020 - **n)))
+            .count();
+        let mut triples = 0;
+        for i in 0..sorted.len() {
+            for j in i + 1..sorted.len() {
+                let rest = 2020_usize.saturating_sub(sorted[i] + sorted[j]);
+                if rest > sorted[j] && set.contains(&rest) {
+                    triples += 1;
+                }
+            }
+        }
+
+        if pairs == 1 && triples == 1 {
+            rng.shuffle(&mut entries);
+            return Generated::known(lines(&entries), a * (2020 - a), x * y * z);
+        }
+    }
+}
+
+fn d02(rng: &mut Rng, count: usize) -> Generated {
+    const LETTERS: [char; 5] = ['a', 'b', 'c', 'd', 'e'];
+
+    let mut policies = Vec::new();
+    let (mut valid1, mut valid2) = (0, 0);
+    for _ in 0..count {
+        let letter = *rng.choose(&LETTERS);
+        let min = rng.range(1, 5);
+        let max = rng.range(min + 1, min + 8);
+        let password: Vec<char> = (0..rng.range(max, max + 6))
+            .map(|_| *rng.choose(&LETTERS))
+            .collect();
+
+        let occurrences = password.iter().filter(|c| **c == letter).count();
+        if (min..=max).contains(&occurrences) {
+            valid1 += 1;
+        }
+        if (password[min - 1] == letter) != (password[max - 1] == letter) {
+            valid2 += 1;
+        }
+        let password: String = password.into_iter().collect();
+        policies.push(format!("{}-{} {}: {}", min, max, letter, password));
+    }
+    Generated::known(lines(&policies), valid1, valid2)
+}
+
+fn d03(rng: &mut Rng, height: usize) -> Generated {
+    let input = grid_text(rng, 31, height, ['.', '#'], 25);
+    let rows: Vec<&[u8]> = input.lines().map(|line| line.as_bytes()).collect();
+    let trees = |right: usize, down: usize| {
+        (0..height)
+            .step_by(down)
+            .enumerate()
+            .filter(|(i, y)| rows[*y][i * right % 31] == b'#')
+            .count()
+    };
+
+    let slopes = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+    let product: usize = slopes.iter().map(|(r, d)| trees(*r, *d)).product();
+    let part1 = trees(3, 1);
+    Generated::known(input, part1, product)
+}
+
+// each passport is valid, has a field missing or has a field with a bad value
+fn d04(rng: &mut Rng, count: usize) -> Generated {
+    const EYES: [&str; 7] = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
+
+    let mut passports = Vec::new();
+    let (mut complete, mut valid) = (0, 0);
+    for _ in 0..count {
+        let hex: String = (0..6)
+            .map(|_| std::char::from_digit(rng.below(16) as u32, 16).unwrap())
+            .collect();
+        let mut fields = vec![
+            ("byr", rng.range(1920, 2002).to_string()),
+            ("iyr", rng.range(2010, 2020).to_string()),
+            ("eyr", rng.range(2020, 2030).to_string()),
+            (
+                "hgt",
+                if rng.percent(50) {
+                    format!("{}cm", rng.range(150, 193))
+                } else {
+                    format!("{}in", rng.range(59, 76))
+                },
+            ),
+            ("hcl", format!("#{}", hex)),
+            ("ecl", rng.choose(&EYES).to_string()),
+            ("pid", format!("{:09}", rng.below(1_000_000_000))),
+        ];
+
+        match rng.below(10) {
+            0..=4 => {
+                complete += 1;
+                valid += 1;
+            }
+            5 | 6 => {
+                let i = rng.below(fields.len());
+                fields.remove(i);
+            }
+            _ => {
+                complete += 1;
+                let i = rng.below(fields.len());
+                fields[i].1 = match fields[i].0 {
+                    "byr" => rng.range(1900, 1919).to_string(),
+                    "iyr" => rng.range(2021, 2029).to_string(),
+                    "eyr" => rng.range(2031, 2040).to_string(),
+                    "hgt" => rng.choose(&["194cm", "58in", "170", "190in"]).to_string(),
+                    "hcl" => rng.choose(&["#12345", "123abc", "#12345z"]).to_string(),
+                    "ecl" => rng.choose(&["xyz", "gmt", "red"]).to_string(),
+                    _ => rng.choose(&["12345678", "0123456789"]).to_string(),
+                };
+            }
+        }
+        if rng.percent(50) {
+            fields.push(("cid", rng.range(100, 350).to_string()));
+        }
+
+        rng.shuffle(&mut fields);
+        let mut passport = String::new();
+        for (i, (key, value)) in fields.iter().enumerate() {
+            if i > 0 {
+                passport.push(if rng.percent(25) { '\n' } else { ' ' });
+            }
+            passport.push_str(&format!("{}:{}", key, value));
+        }
+        passports.push(passport);
+    }
+    Generated::known(passports.join("\n\n"), complete, valid)
+}
+
+// every seat from low to high but one
+fn d05(rng: &mut Rng, count: usize) -> Generated {
+    let low = rng.range(0, 1023 - count);
+    let high = low + count;
+    let missing = rng.range(low + 1, high - 1);
+
+    let mut passes: Vec<String> = (low..=high)
+        .filter(|id| *id != missing)
+        .map(|id| {
+            let row = (0..7)
+                .rev()
+                .map(|bit| if id >> (bit + 3) & 1 == 1 { 'B' } else { 'F' });
+            let col = (0..3)
+                .rev()
+                .map(|bit| if id >> bit & 1 == 1 { 'R' } else { 'L' });
+            row.chain(col).collect()
+        })
+        .collect();
+    rng.shuffle(&mut passes);
+    Generated::known(lines(&passes), high, missing)
+}
+
+fn d06(rng: &mut Rng, count: usize) -> Generated {
+    let mut groups = Vec::new();
+    let (mut anyone, mut everyone) = (0, 0);
+    for _ in 0..count {
+        let size = rng.range(1, 10);
+        let pool = rng.sample(0, 25, size);
+        let people: Vec<Vec<usize>> = (0..rng.range(1, 5))
+            .map(|_| {
+                let mut answers: Vec<usize> =
+                    pool.iter().copied().filter(|_| rng.percent(60)).collect();
+                if answers.is_empty() {
+                    answers.push(*rng.choose(&pool));
+                }
+                answers
+            })
+            .collect();
+
+        anyone += pool
+            .iter()
+            .filter(|q| people.iter().any(|p| p.contains(q)))
+            .count();
+        everyone += pool
+            .iter()
+            .filter(|q| people.iter().all(|p| p.contains(q)))
+            .count();
+        let people: Vec<String> = people
+            .iter()
+            .map(|p| p.iter().map(|q| (b'a' + *q as u8) as char).collect())
+            .collect();
+        groups.push(lines(&people));
+    }
+    Generated::known(groups.join("\n\n"), anyone, everyone)
+}
+
+// bags are put in layers and only hold bags from the next layer, so the rules can't loop
+fn d07(rng: &mut Rng, count: usize) -> Generated {
+    const ADJECTIVES: [&str; 18] = [
+        "bright", "clear", "dark", "dim", "dotted", "drab", "dull", "faded", "light", "mirrored",
+        "muted", "pale", "plaid", "posh", "shiny", "striped", "vibrant", "wavy",
+    ];
+    const COLOURS: [&str; 20] = [
+        "aqua",
+        "beige",
+        "black",
+        "blue",
+        "bronze",
+        "chartreuse",
+        "coral",
+        "crimson",
+        "cyan",
+        "fuchsia",
+        "gold",
+        "green",
+        "indigo",
+        "lavender",
+        "lime",
+        "maroon",
+        "olive",
+        "plum",
+        "tan",
+        "teal",
+    ];
+    const LAYERS: usize = 5;
+
+    let mut names: Vec<String> = ADJECTIVES
+        .iter()
+        .flat_map(|a| COLOURS.iter().map(move |c| format!("{} {}", a, c)))
+        .filter(|name| name != "shiny gold")
+        .collect();
+    rng.shuffle(&mut names);
+    names.truncate(count - 1);
+    // shiny gold goes in the middle layer
+    names.insert(2 * count / LAYERS, "shiny gold".to_string());
+    let layer = |i: usize| i * LAYERS / count;
+
+    let mut contents: Vec<Vec<(usize, usize)>> = vec![Vec::new(); count];
+    for (i, content) in contents.iter_mut().enumerate() {
+        let next: Vec<usize> = (0..count).filter(|j| layer(*j) == layer(i) + 1).collect();
+        if next.is_empty() || rng.percent(15) {
+            continue;
+        }
+        let mut held = next.clone();
+        rng.shuffle(&mut held);
+        held.truncate(rng.range(1, 3));
+        *content = held.into_iter().map(|j| (rng.range(1, 4), j)).collect();
+    }
+
+    let gold = names.iter().position(|name| name == "shiny gold").unwrap();
+    let mut outer = HashSet::new();
+    let mut to_visit = vec![gold];
+    while let Some(bag) = to_visit.pop() {
+        for (i, content) in contents.iter().enumerate() {
+            if content.iter().any(|(_, j)| *j == bag) && outer.insert(i) {
+                to_visit.push(i);
+            }
+        }
+    }
+
+    // the bags inside each bag, filled in from the last layer back
+    let mut inside = vec![0; count];
+    for i in (0..count).rev() {
+        inside[i] = contents[i].iter().map(|(n, j)| n * (1 + inside[*j])).sum();
+    }
+
+    let mut rules: Vec<String> = contents
+        .iter()
+        .enumerate()
+        .map(|(i, content)| {
+            let content = if content.is_empty() {
+                "no other bags".to_string()
+            } else {
+                content
+                    .iter()
+                    .map(|(n, j)| {
+                        format!("{} {} bag{}", n, names[*j], if *n == 1 { "" } else { "s" })
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            };
+            format!("{} bags contain {}.", names[i], content)
+        })
+        .collect();
+    rng.shuffle(&mut rules);
+    Generated::known(lines(&rules), outer.len(), inside[gold])
+}
+
+// whether the program halts, and the accumulator when it halts or loops
+fn run_d08(program: &[Instruction]) -> (bool, i64) {
+    let mut cpu = Cpu::new(program.to_vec());
+    let halted = matches!(cpu.run(), CpuState::Halted { .. });
+    (halted, cpu.get_acc())
+}
+
+// a program with only forward jumps, then one nop on its path turned into a jump back
+fn d08(rng: &mut Rng, len: usize) -> Generated {
+    let instruction = |op, val| Instruction { op, val };
+    loop {
+        let mut program: Vec<Instruction> = (0..len)
+            .map(|i| match rng.below(4) {
+                0 | 1 => instruction(OpCode::Acc, rng.range(0, 100) as i64 - 50),
+                2 => instruction(OpCode::Nop, rng.range(0, 40) as i64 - 20),
+                _ => instruction(OpCode::Jmp, rng.range(1, (len - i).min(5)) as i64),
+            })
+            .collect();
+
+        let mut path = Vec::new();
+        let mut ip = 0;
+        while ip < len {
+            path.push(ip);
+            ip = match program[ip].op {
+                OpCode::Jmp => ip + program[ip].val as usize,
+                _ => ip + 1,
+            };
+        }
+        let nops: Vec<usize> = (1..path.len())
+            .filter(|k| program[path[*k]].op == OpCode::Nop)
+            .collect();
+        if nops.is_empty() {
+            continue;
+        }
+        let k = *rng.choose(&nops);
+        let back = path[k] - path[rng.below(k)];
+        program[path[k]] = instruction(OpCode::Jmp, -(back as i64));
+
+        let (_, looped_acc) = run_d08(&program);
+        let mut fixes = Vec::new();
+        for i in 0..len {
+            let original = program[i];
+            program[i].op = match original.op {
+                OpCode::Jmp => OpCode::Nop,
+                OpCode::Nop => OpCode::Jmp,
+                _ => continue,
+            };
+            if let (true, acc) = run_d08(&program) {
+                fixes.push(acc);
+            }
+            program[i] = original;
+        }
+
+        if fixes.len() == 1 {
+            return Generated::known(lines(&program), looped_acc, fixes[0]);
+        }
+    }
+}
+
+// every number is the sum of two small ones among the 25 before it, except a planted one
+// that is the sum of a contiguous run instead
+fn d09(rng: &mut Rng, len: usize) -> Generated {
+    fn next(rng: &mut Rng, entries: &[u64]) -> u64 {
+        let mut window = entries[entries.len() - 25..].to_vec();
+        window.sort_unstable();
+        window.dedup();
+        let a = rng.below(5);
+        let b = (a + rng.range(1, 4)) % 5;
+        window[a] + window[b]
+    }
+
+    loop {
+        let mut entries: Vec<u64> = rng.sample(1, 50, 25).iter().map(|n| *n as u64).collect();
+        let invalid_at = rng.range((len / 2).max(30), len - 1);
+        while entries.len() < invalid_at {
+            let n = next(rng, &entries);
+            entries.push(n);
+        }
+
+        let run = rng.range(2, 8);
+        let start = rng.range(0, invalid_at - run);
+        let target: u64 = entries[start..start + run].iter().sum();
+        let window = &entries[invalid_at - 25..];
+        let is_sum = window
+            .iter()
+            .enumerate()
+            .any(|(i, a)| window[i + 1..].iter().any(|b| a + b == target));
+        // no earlier run may add up to it either
+        let earlier = (0..start).any(|s| {
+            let mut sum = 0;
+            entries[s..]
+                .iter()
+                .map(|n| {
+                    sum += n;
+                    sum
+                })
+                .take_while(|sum| *sum <= target)
+                .skip(1)
+                .any(|sum| sum == target)
+        });
+        if is_sum || earlier {
+            continue;
+        }
+
+        let weakness = entries[start..start + run].iter().min().unwrap()
+            + entries[start..start + run].iter().max().unwrap();
+        entries.push(target);
+        while entries.len() < len {
+            let n = next(rng, &entries);
+            entries.push(n);
+        }
+        return Generated::known(lines(&entries), target, weakness);
+    }
+}
+
+// runs of up to 5 adapters 1 jolt apart, separated by gaps of 3
+fn d10(rng: &mut Rng, count: usize) -> Generated {
+    const WAYS: [u64; 5] = [1, 1, 2, 4, 7];
+
+    let mut adapters = Vec::new();
+    let (mut ones, mut threes, mut ways) = (0, 0, 1_u64);
+    let mut joltage = 0;
+    let mut first = true;
+    while adapters.len() < count {
+        let run = rng.range(1, 5);
+        if !first {
+            joltage += 3;
+            threes += 1;
+            adapters.push(joltage);
+        }
+        for _ in 1..run {
+            joltage += 1;
+            ones += 1;
+            adapters.push(joltage);
+        }
+        ways *= WAYS[run - 1];
+        first = false;
+    }
+    // the device is 3 above the last adapter
+    threes += 1;
+
+    rng.shuffle(&mut adapters);
+    Generated::known(lines(&adapters), ones * threes, ways)
+}
+
+// whether the seats stop changing, which the solver waits for
+fn settles<T: Topology>(topology: T, rule: &str) -> bool {
+    let mut seats = Automaton::new(topology, rule.parse().unwrap(), vec![]);
+    (0..1000).any(|_| !seats.step())
+}
+
+// some layouts make seats blink forever, only the ones that settle are kept
+fn d11(rng: &mut Rng, side: usize) -> Generated {
+    loop {
+        let input = grid_text(rng, side, side, ['L', '.'], 25);
+        let mask = Grid::parse(11, &input, |c| Some(c == 'L')).unwrap();
+        if settles(Bounded::adjacent(&mask), "B0/S0123")
+            && settles(Bounded::line_of_sight(&mask), "B0/S01234")
+        {
+            return Generated::unknown(input);
+        }
+    }
+}
+
+fn d12(rng: &mut Rng, count: usize) -> Generated {
+    let instructions: Vec<String> = (0..count)
+        .map(|_| match rng.below(7) {
+            0..=3 => format!("{}{}", ['N', 'S', 'E', 'W'][rng.below(4)], rng.range(1, 20)),
+            4 => format!("F{}", rng.range(1, 100)),
+            _ => format!("{}{}", ['L', 'R'][rng.below(2)], 90 * rng.range(1, 3)),
+        })
+        .collect();
+    Generated::unknown(lines(&instructions))
+}
+
+// the timestamp is picked first and each bus placed at an offset that fits it
+fn d13(rng: &mut Rng, count: usize) -> Generated {
+    const PRIMES: [usize; 20] = [
+        7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83,
+    ];
+
+    let ids: Vec<usize> = rng
+        .sample(0, PRIMES.len() - 1, count)
+        .iter()
+        .map(|i| PRIMES[*i])
+        .collect();
+    let product: usize = ids.iter().product();
+    let timestamp = ids[0] * rng.range(1, product / ids[0] - 1);
+
+    let mut slots: HashMap<usize, usize> = HashMap::new();
+    slots.insert(0, ids[0]);
+    for id in &ids[1..] {
+        let mut offset = (id - timestamp % id) % id;
+        while offset == 0 || slots.contains_key(&offset) {
+            offset += id;
+        }
+        slots.insert(offset, *id);
+    }
+    let last = *slots.keys().max().unwrap();
+    let busses: Vec<String> = (0..=last)
+        .map(|offset| {
+            slots
+                .get(&offset)
+                .map_or("x".to_string(), |id| id.to_string())
+        })
+        .collect();
+
+    let mut earliest = rng.range(100_000, 1_000_000);
+    while ids.iter().any(|id| earliest.is_multiple_of(*id)) {
+        earliest += 1;
+    }
+    let mut best = (usize::MAX, 0);
+    for offset in 0..=last {
+        if let Some(id) = slots.get(&offset) {
+            let wait = id - earliest % id;
+            if wait < best.0 {
+                best = (wait, *id);
+            }
+        }
+    }
+
+    let input = format!("{}\n{}", earliest, busses.join(","));
+    Generated::known(input, best.0 * best.1, timestamp)
+}
+
+// few floating bits, so part 2 doesn't write too many addresses
+fn d14(rng: &mut Rng, count: usize) -> Generated {
+    let mut program = Vec::new();
+    for _ in 0..count {
+        let size = rng.range(0, 6);
+        let floating = rng.sample(0, 35, size);
+        let mask: String = (0..36)
+            .map(|i| {
+                if floating.contains(&i) {
+                    'X'
+                } else {
+                    ['0', '1'][rng.below(2)]
+                }
+            })
+            .collect();
+        program.push(format!("mask = {}", mask));
+        for _ in 0..rng.range(1, 4) {
+            let value = rng.next_u64() % (1 << 36);
+            program.push(format!("mem[{}] = {}", rng.range(0, 65535), value));
+        }
+    }
+    Generated::unknown(lines(&program))
+}
+
+fn d15(rng: &mut Rng, count: usize) -> Generated {
+    Generated::unknown(
+        rng.sample(0, 19, count)
+            .iter()
+            .map(|n| n.to_string())
+            .collect::<Vec<String>>()
+            .join(","),
+    )
+}
+
+// Field ranges form a staircase: the column of the field ranked r holds values up to 100 + 40r
+// and the field accepts values up to that, so only one assignment works. Invalid values are
+// above every range.
+fn d16(rng: &mut Rng, count: usize) -> Generated {
+    const FIELDS: [&str; 20] = [
+        "departure location",
+        "departure station",
+        "departure platform",
+        "departure track",
+        "departure date",
+        "departure time",
+        "arrival location",
+        "arrival station",
+        "arrival platform",
+        "arrival track",
+        "class",
+        "duration",
+        "price",
+        "route",
+        "row",
+        "seat",
+        "train",
+        "type",
+        "wagon",
+        "zone",
+    ];
+    let n = FIELDS.len();
+    let top = |rank: usize| 100 + 40 * rank;
+
+    // rank of every field, and the field in every column
+    let ranks = rng.sample(0, n - 1, n);
+    let columns = rng.sample(0, n - 1, n);
+    let ticket = |rng: &mut Rng| -> Vec<usize> {
+        columns
+            .iter()
+            .map(|field| rng.range(n, top(ranks[*field])))
+            .collect()
+    };
+
+    let mut rules: Vec<String> = FIELDS
+        .iter()
+        .enumerate()
+        .map(|(field, name)| {
+            let rank = ranks[field];
+            let extra = 900 + 3 * rank;
+            format!(
+                "{}: {}-{} or {}-{}",
+                name,
+                rank + 1,
+                top(rank),
+                extra,
+                extra + 2
+            )
+        })
+        .collect();
+    rng.shuffle(&mut rules);
+
+    let mine = ticket(rng);
+    let mut error_rate = 0;
+    let mut nearby: Vec<Vec<usize>> = (0..count).map(|_| ticket(rng)).collect();
+    // every column needs its largest value somewhere, or it would fit the field below it
+    for (column, field) in columns.iter().enumerate() {
+        nearby[column % count][column] = top(ranks[*field]);
+    }
+    for _ in 0..count / 4 {
+        let invalid = rng.range(990, 999);
+        error_rate += invalid;
+        let mut tickets = ticket(rng);
+        tickets[rng.below(n)] = invalid;
+        nearby.insert(rng.range(0, nearby.len()), tickets);
+    }
+
+    let departures: usize = columns
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| FIELDS[**field].starts_with("departure"))
+        .map(|(column, _)| mine[column])
+        .product();
+
+    let format = |ticket: &Vec<usize>| {
+        ticket
+            .iter()
+            .map(|n| n.to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    };
+    let nearby: Vec<String> = nearby.iter().map(format).collect();
+    let input = format!(
+        "{}\n\nyour ticket:\n{}\n\nnearby tickets:\n{}",
+        lines(&rules),
+        format(&mine),
+        lines(&nearby)
+    );
+    Generated::known(input, error_rate, departures)
+}
+
+fn d17(rng: &mut Rng, side: usize) -> Generated {
+    Generated::unknown(grid_text(rng, side, side, ['.', '#'], 40))
+}
+
+// an expression and its values with equal precedence and with + first
+fn d18_expression(rng: &mut Rng, depth: usize) -> (String, u64, u64) {
+    let mut text = String::new();
+    let (mut left_to_right, mut product, mut sum) = (0, 1, 0);
+    for i in 0..rng.range(2, 4) {
+        let (term, value1, value2) = if depth < 2 && rng.percent(25) {
+            let (text, value1, value2) = d18_expression(rng, depth + 1);
+            (format!("({})", text), value1, value2)
+        } else {
+            let n = rng.range(1, 9) as u64;
+            (n.to_string(), n, n)
+        };
+
+        if i == 0 {
+            left_to_right = value1;
+            sum = value2;
+        } else if rng.percent(50) {
+            text.push_str(" + ");
+            left_to_right += value1;
+            sum += value2;
+        } else {
+            text.push_str(" * ");
+            left_to_right *= value1;
+            product *= sum;
+            sum = value2;
+        }
+        text.push_str(&term);
+    }
+    (text, left_to_right, product * sum)
+}
+
+fn d18(rng: &mut Rng, count: usize) -> Generated {
+    let mut expressions = Vec::new();
+    let (mut total1, mut total2) = (0, 0);
+    for _ in 0..count {
+        let (text, value1, value2) = d18_expression(rng, 0);
+        expressions.push(text);
+        total1 += value1;
+        total2 += value2;
+    }
+    Generated::known(lines(&expressions), total1, total2)
+}
+
+// Rules 42 and 31 split the strings of a's and b's of one length between them, so whether a
+// message matches is decided by which of the two each block of it belongs to.
+fn d19(rng: &mut Rng, count: usize) -> Generated {
+    struct Rules<'a> {
+        rng: &'a mut Rng,
+        ids: Vec<usize>,
+        rules: Vec<String>,
+        labels: Vec<bool>,
+        block: usize,
+        chars: [usize; 2],
+    }
+
+    impl Rules<'_> {
+        // the rule for the rest of the strings after `prefix` that have this label
+        fn build(
+            &mut self,
+            prefix: usize,
+            depth: usize,
+            label: bool,
+            id: Option<usize>,
+        ) -> Option<usize> {
+            let mut alternatives = Vec::new();
+            for c in 0..2 {
+                let next = prefix * 2 + c;
+                if depth + 1 == self.block {
+                    if self.labels[next] == label {
+                        alternatives.push(self.chars[c].to_string());
+                    }
+                } else if let Some(rest) = self.build(next, depth + 1, label, None) {
+                    alternatives.push(format!("{} {}", self.chars[c], rest));
+                }
+            }
+            if alternatives.is_empty() {
+                return None;
+            }
+            let id = id.unwrap_or_else(|| self.ids.pop().unwrap());
+            self.rules
+                .push(format!("{}: {}", id, alternatives.join(" | ")));
+            Some(id)
+        }
+
+        fn message_block(&mut self, label: bool) -> String {
+            let strings: Vec<usize> = (0..self.labels.len())
+                .filter(|s| self.labels[*s] == label)
+                .collect();
+            let s = *self.rng.choose(&strings);
+            (0..self.block)
+                .rev()
+                .map(|bit| if s >> bit & 1 == 1 { 'b' } else { 'a' })
+                .collect()
+        }
+    }
+
+    let block = rng.range(3, 5);
+    let mut labels: Vec<bool> = (0..1 << block).map(|_| rng.percent(50)).collect();
+    labels[0] = true;
+    labels[1] = false;
+    let mut ids: Vec<usize> = (1..200)
+        .filter(|id| ![8, 11, 31, 42].contains(id))
+        .collect();
+    rng.shuffle(&mut ids);
+    let chars = [ids.pop().unwrap(), ids.pop().unwrap()];
+
+    let mut rules = Rules {
+        rng,
+        ids,
+        rules: vec![
+            "0: 8 11".to_string(),
+            "8: 42".to_string(),
+            "11: 42 31".to_string(),
+            format!("{}: \"a\"", chars[0]),
+            format!("{}: \"b\"", chars[1]),
+        ],
+        labels,
+        block,
+        chars,
+    };
+    rules.build(0, 0, true, Some(42));
+    rules.build(0, 0, false, Some(31));
+
+    let mut messages = Vec::new();
+    let (mut matches1, mut matches2) = (0, 0);
+    for _ in 0..count {
+        let kind = rules.rng.below(3);
+        let blocks: Vec<bool> = if kind == 0 {
+            let k = rules.rng.range(1, 3);
+            let m = rules.rng.range(k + 1, 5);
+            (0..m + k).map(|i| i < m).collect()
+        } else {
+            (0..rules.rng.range(2, 7))
+                .map(|_| rules.rng.percent(60))
+                .collect()
+        };
+
+        let mut message: String = blocks.iter().map(|b| rules.message_block(*b)).collect();
+        if kind == 2 {
+            message.push_str(&rules.message_block(true)[1..]);
+        } else {
+            let forty_twos = blocks.iter().take_while(|b| **b).count();
+            let thirty_ones = blocks.len() - forty_twos;
+            if blocks[forty_twos..].iter().all(|b| !*b) {
+                if forty_twos == 2 && thirty_ones == 1 {
+                    matches1 += 1;
+                }
+                if forty_twos > thirty_ones && thirty_ones >= 1 {
+                    matches2 += 1;
+                }
+            }
+        }
+        messages.push(message);
+    }
+
+    let mut rule_lines = rules.rules;
+    let rng = rules.rng;
+    rng.shuffle(&mut rule_lines);
+    let input = format!("{}\n\n{}", lines(&rule_lines), lines(&messages));
+    Generated::known(input, matches1, matches2)
+}
+
+// Fills in the tile borders of a grid cut every `step` cells so that no two tile edges are
+// the same, even flipped. Like the puzzle's inputs no edge reads the same both ways, which
+// would leave a tile's orientation open. Gives up if it runs out of edges.
+fn random_borders(rng: &mut Rng, big: &mut Grid<bool>, step: usize) -> bool {
+    // None for an edge that reads the same both ways
+    let signature = |edge: Vec<bool>| {
+        let reversed: Vec<bool> = edge.iter().rev().copied().collect();
+        if reversed == edge {
+            None
+        } else {
+            Some(reversed.min(edge))
+        }
+    };
+
+    for (x, y) in big.positions().collect::<Vec<_>>() {
+        if x % step == 0 && y % step == 0 {
+            big.set(x, y, rng.percent(50));
+        }
+    }
+    let mut used = HashSet::new();
+    for y in (0..big.height()).step_by(step) {
+        for x in (0..big.width()).step_by(step) {
+            // the edge going right from this corner, then the one going down
+            for (dx, dy) in [(1, 0), (0, 1)].iter() {
+                if x + dx * step >= big.width() || y + dy * step >= big.height() {
+                    continue;
+                }
+                let cell = |k: usize| (x + dx * k, y + dy * k);
+                let mut attempts = 0;
+                loop {
+                    for k in 1..step {
+                        big[cell(k)] = rng.percent(50);
+                    }
+                    let edge = (0..=step).map(|k| big[cell(k)]).collect();
+                    if signature(edge).is_some_and(|edge| used.insert(edge)) {
+                        break;
+                    }
+                    attempts += 1;
+                    if attempts == 1000 {
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+    true
+}
+
+// An image with sea monsters cut into tiles that overlap by their borders, then every tile
+// turned and shuffled.
+fn d20(rng: &mut Rng, side: usize) -> Generated {
+    const MONSTER: [&str; 3] = [
+        "                  # ",
+        "#    ##    ##    ###",
+        " #  #  #  #  #  #   ",
+    ];
+    let monster = Grid::parse(20, &MONSTER.join("\n"), |c| Some(c == '#')).unwrap();
+    let inner = 8;
+    let width = side * inner;
+
+    loop {
+        let mut image = Grid::new(width, width, false);
+        for y in 0..width {
+            for x in 0..width {
+                image.set(x, y, rng.percent(30));
+            }
+        }
+        let mut planted = Vec::new();
+        for _ in 0..side {
+            let x = rng.range(0, width - monster.width());
+            let y = rng.range(0, width - monster.height());
+            if planted.iter().all(|(px, py): &(usize, usize)| {
+                x + monster.width() <= *px
+                    || *px + monster.width() <= x
+                    || y + monster.height() <= *py
+                    || *py + monster.height() <= y
+            }) {
+                planted.push((x, y));
+                for ((mx, my), m) in monster.iter() {
+                    if *m {
+                        image.set(x + mx, y + my, true);
+                    }
+                }
+            }
+        }
+
+        // the planted monsters must be the only ones
+        let found: Vec<usize> = image
+            .orientations()
+            .iter()
+            .map(|image| image.find_pattern(&monster, |m, cell| !*m || *cell).len())
+            .collect();
+        if found[0] != planted.len() || found[1..].iter().any(|n| *n > 0) {
+            continue;
+        }
+
+        // tiles share their borders, the image is what is inside them
+        let step = inner + 1;
+        let full = step * side + 1;
+        let mut big = Grid::new(full, full, false);
+        for ((x, y), cell) in image.iter() {
+            big.set(x + x / inner + 1, y + y / inner + 1, *cell);
+        }
+        if !random_borders(rng, &mut big, step) {
+            continue;
+        }
+
+        let ids = rng.sample(1000, 9999, side * side);
+        let mut tiles = Vec::new();
+        for ty in 0..side {
+            for tx in 0..side {
+                let tile = big.crop(tx * step, ty * step, step + 1, step + 1);
+                let tile = rng.choose(&tile.orientations()).clone();
+                tiles.push((ids[ty * side + tx], (tx, ty), tile));
+            }
+        }
+
+        let corners: u64 = tiles
+            .iter()
+            .filter(|(_, (x, y), _)| (*x == 0 || *x == side - 1) && (*y == 0 || *y == side - 1))
+            .map(|(id, _, _)| *id as u64)
+            .product();
+        let monster_cells = monster.iter().filter(|(_, m)| **m).count();
+        let rough = image.iter().filter(|(_, cell)| **cell).count() - planted.len() * monster_cells;
+
+        rng.shuffle(&mut tiles);
+        let tiles: Vec<String> = tiles
+            .iter()
+            .map(|(id, _, tile)| {
+                let text = tile.map(|cell| if *cell { '#' } else { '.' });
+                format!("Tile {}:\n{}", id, text)
+            })
+            .collect();
+        return Generated::known(tiles.join("\n\n"), corners, rough);
+    }
+}
+
+// Each allergen gets two foods that only list it and share no other ingredient, which pins
+// it to a single ingredient. The other foods are random.
+fn d21(rng: &mut Rng, count: usize) -> Generated {
+    const ALLERGENS: [&str; 8] = [
+        "dairy",
+        "eggs",
+        "fish",
+        "nuts",
+        "peanuts",
+        "sesame",
+        "shellfish",
+        "soy",
+    ];
+
+    let size = rng.range(2, ALLERGENS.len());
+    let allergens = rng.sample(0, ALLERGENS.len() - 1, size);
+    let mut names = HashSet::new();
+    while names.len() < allergens.len() + 3 * allergens.len() + 10 {
+        let name: String = (0..rng.range(4, 8))
+            .map(|_| (b'a' + rng.below(26) as u8) as char)
+            .collect();
+        names.insert(name);
+    }
+    let mut names: Vec<String> = names.into_iter().collect();
+    names.sort();
+    rng.shuffle(&mut names);
+    let (unsafe_names, safe) = names.split_at(allergens.len());
+
+    let mut foods: Vec<(Vec<String>, Vec<usize>)> = Vec::new();
+    for (i, allergen) in allergens.iter().enumerate() {
+        let mut pool = safe.to_vec();
+        rng.shuffle(&mut pool);
+        let half = pool.len() / 2;
+        for part in [&pool[..half], &pool[half..]].iter() {
+            let mut ingredients: Vec<String> = part[..rng.range(1, 4)].to_vec();
+            ingredients.push(unsafe_names[i].clone());
+            foods.push((ingredients, vec![*allergen]));
+        }
+    }
+    for _ in 0..count {
+        let mut listed = allergens.clone();
+        rng.shuffle(&mut listed);
+        listed.truncate(rng.range(1, 3.min(allergens.len())));
+        let size = rng.range(1, 6);
+        let mut ingredients: Vec<String> = rng
+            .sample(0, safe.len() - 1, size)
+            .iter()
+            .map(|i| safe[*i].clone())
+            .collect();
+        for (i, allergen) in allergens.iter().enumerate() {
+            // allergens aren't always listed
+            if listed.contains(allergen) || rng.percent(10) {
+                ingredients.push(unsafe_names[i].clone());
+            }
+        }
+        foods.push((ingredients, listed));
+    }
+
+    let safe_count = foods
+        .iter()
+        .map(|(ingredients, _)| ingredients.iter().filter(|i| safe.contains(i)).count())
+        .sum::<usize>();
+    let mut dangerous: Vec<(&str, &String)> = allergens
+        .iter()
+        .enumerate()
+        .map(|(i, allergen)| (ALLERGENS[*allergen], &unsafe_names[i]))
+        .collect();
+    dangerous.sort();
+    let dangerous: Vec<&str> = dangerous.iter().map(|(_, name)| name.as_str()).collect();
+
+    rng.shuffle(&mut foods);
+    let foods: Vec<String> = foods
+        .iter_mut()
+        .map(|(ingredients, listed)| {
+            rng.shuffle(ingredients);
+            let listed: Vec<&str> = listed.iter().map(|a| ALLERGENS[*a]).collect();
+            format!("{} (contains {})", ingredients.join(" "), listed.join(", "))
+        })
+        .collect();
+    Generated::known(lines(&foods), safe_count, dangerous.join(","))
+}
+
+fn d22(rng: &mut Rng, count: usize) -> Generated {
+    let mut cards: Vec<usize> = (1..=2 * count).collect();
+    rng.shuffle(&mut cards);
+    let (one, two) = cards.split_at(count);
+    Generated::unknown(format!(
+        "Player 1:\n{}\n\nPlayer 2:\n{}",
+        lines(one),
+        lines(two)
+    ))
+}
+
+fn d23(rng: &mut Rng) -> Generated {
+    let mut cups: Vec<usize> = (1..=9).collect();
+    rng.shuffle(&mut cups);
+    Generated::unknown(cups.iter().map(|c| c.to_string()).collect())
+}
+
+// walks to target tiles padded with steps that cancel out, some targets more than once
+fn d24(rng: &mut Rng, count: usize) -> Generated {
+    const CANCELLING: [[&str; 2]; 3] = [["e", "w"], ["ne", "sw"], ["nw", "se"]];
+
+    let targets: Vec<(i64, i64)> = (0..count / 2 + 1)
+        .map(|_| (rng.range(0, 20) as i64 - 10, rng.range(0, 20) as i64 - 10))
+        .collect();
+
+    let mut flips: HashMap<(i64, i64), usize> = HashMap::new();
+    let mut walks = Vec::new();
+    for _ in 0..count {
+        let (q, r) = *rng.choose(&targets);
+        *flips.entry((q, r)).or_insert(0) += 1;
+
+        let mut steps = Vec::new();
+        steps.extend(std::iter::repeat_n(
+            if q > 0 { "e" } else { "w" },
+            q.unsigned_abs() as usize,
+        ));
+        steps.extend(std::iter::repeat_n(
+            if r > 0 { "se" } else { "nw" },
+            r.unsigned_abs() as usize,
+        ));
+        for _ in 0..rng.range(0, 4) {
+            let pair = rng.choose(&CANCELLING);
+            steps.extend_from_slice(pair);
+        }
+        rng.shuffle(&mut steps);
+        walks.push(steps.concat());
+    }
+
+    let black = flips.values().filter(|n| *n % 2 == 1).count();
+    Generated {
+        input: lines(&walks),
+        part1: Some(black.into()),
+        part2: None,
+    }
+}
+
+fn d25(rng: &mut Rng, size: usize) -> Generated {
+    let transform = |subject: u64, loop_size: usize| {
+        (0..loop_size).fold(1, |value, _| value * subject % 20201227)
+    };
+    let card_loop = rng.range(1, 1000 * size);
+    let door_loop = rng.range(1, 1000 * size);
+    let card = transform(7, card_loop);
+    let door = transform(7, door_loop);
+
+    Generated {
+        input: format!("{}\n{}", card, door),
+        part1: Some(transform(door, card_loop).into()),
+        part2: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::{parts, solve, DAYS};
+
+    #[test]
+    fn known_answers() {
+        for day in DAYS.iter().copied() {
+            for seed in 0..3 {
+                let generated = generate(day, seed, 20).unwrap();
+                assert_eq!(generated, generate(day, seed, 20).unwrap());
+
+                let input = &generated.input;
+                assert!(solve(day, 1, input).is_ok(), "day {} seed {}", day, seed);
+                for part in 1..=parts(day) {
+                    if let Some(expected) = generated.answer(part) {
+                        let actual = solve(day, part, input);
+                        let message = format!("day {} part {} seed {}", day, part, seed);
+                        assert_eq!(Ok(expected.clone()), actual, "{}", message);
+                    }
+                }
+            }
+        }
+        assert_eq!(Err(Error::UnknownDay(26)), generate(26, 0, 1));
+    }
+}
@@ -0,0 +1,384 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Index, IndexMut};
+
+use crate::error::{char_at, ParseError};
+
+// A rectangular grid of cells, stored row by row. Positions are (x, y) with x the column,
+// counted from the top left corner.
+
+pub const ORTHOGONAL: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+pub const ADJACENT: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn from_cells(width: usize, cells: Vec<T>) -> Self {
+        assert!(
+            width > 0 && cells.len().is_multiple_of(width),
+            "cells don't fill whole rows"
+        );
+        Grid {
+            width,
+            height: cells.len() / width,
+            cells,
+        }
+    }
+
+    // one row per line, f maps each character to a cell or None if it isn't valid
+    pub fn parse<F>(day: u32, input: &str, f: F) -> Result<Self, ParseError>
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        Self::parse_in(day, input, input, f)
+    }
+
+    // like parse, for a grid that is only part of the input, `text` must be a slice of `input`
+    pub fn parse_in<F>(day: u32, input: &str, text: &str, mut f: F) -> Result<Self, ParseError>
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = Vec::new();
+        for line in text.lines() {
+            let before = cells.len();
+            for (i, c) in line.char_indices() {
+                let cell = f(c)
+                    .ok_or_else(|| ParseError::at(day, input, char_at(line, i), "unknown cell"))?;
+                cells.push(cell);
+            }
+
+            let row_width = cells.len() - before;
+            match width {
+                None => width = Some(row_width),
+                Some(width) if width != row_width => {
+                    let message = format!("expected {} columns", width);
+                    return Err(ParseError::at(day, input, line, message));
+                }
+                _ => {}
+            }
+            height += 1;
+        }
+
+        Ok(Grid {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x < self.width && y < self.height {
+            self.cells.get(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if x < self.width && y < self.height {
+            self.cells.get_mut(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: T) {
+        self[(x, y)] = value;
+    }
+
+    // wrapping mode, positions off one edge continue from the opposite edge
+    pub fn get_wrapped(&self, x: isize, y: isize) -> &T {
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        &self[(x, y)]
+    }
+
+    // the position one step in direction (dx, dy), if it is inside the grid
+    pub fn offset(&self, x: usize, y: usize, dx: isize, dy: isize) -> Option<(usize, usize)> {
+        let x = x as isize + dx;
+        let y = y as isize + dy;
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            Some((x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
+    pub fn neighbours4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        ORTHOGONAL
+            .iter()
+            .filter_map(move |(dx, dy)| self.offset(x, y, *dx, *dy))
+    }
+
+    pub fn neighbours8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        ADJACENT
+            .iter()
+            .filter_map(move |(dx, dy)| self.offset(x, y, *dx, *dy))
+    }
+
+    // the positions seen looking from (x, y) in direction (dx, dy), nearest first
+    pub fn ray(
+        &self,
+        x: usize,
+        y: usize,
+        dx: isize,
+        dy: isize,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut pos = (x, y);
+        std::iter::from_fn(move || {
+            pos = self.offset(pos.0, pos.1, dx, dy)?;
+            Some(pos)
+        })
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |i| (i % width, i / width))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn map<U, F>(&self, f: F) -> Grid<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    // a quarter turn clockwise
+    pub fn rotate(&self) -> Self {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for y in 0..self.width {
+            for x in 0..self.height {
+                cells.push(self[(y, self.height - 1 - x)].clone());
+            }
+        }
+        Grid {
+            width: self.height,
+            height: self.width,
+            cells,
+        }
+    }
+
+    // mirrored left to right
+    pub fn flip(&self) -> Self {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for row in self.rows() {
+            cells.extend(row.iter().rev().cloned());
+        }
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells,
+        }
+    }
+
+    // the 4 rotations of the grid and then the 4 rotations of its mirror image, some may be
+    // the same for symmetric grids
+    pub fn orientations(&self) -> Vec<Self> {
+        let mut orientations = Vec::with_capacity(8);
+        for start in [self.clone(), self.flip()].iter() {
+            let mut grid = start.clone();
+            for _ in 0..4 {
+                let next = grid.rotate();
+                orientations.push(grid);
+                grid = next;
+            }
+        }
+        orientations
+    }
+
+    pub fn row(&self, y: usize) -> Vec<T> {
+        self.rows().nth(y).expect("row outside the grid").to_vec()
+    }
+
+    pub fn column(&self, x: usize) -> Vec<T> {
+        (0..self.height).map(|y| self[(x, y)].clone()).collect()
+    }
+
+    // the part of the grid with its top left corner at (x, y)
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Self {
+        assert!(
+            x + width <= self.width && y + height <= self.height,
+            "crop outside the grid"
+        );
+        let mut cells = Vec::with_capacity(width * height);
+        for row in self.rows().skip(y).take(height) {
+            cells.extend_from_slice(&row[x..x + width]);
+        }
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    // The top left positions where the pattern fits, `matches` is given each pattern cell
+    // with the grid cell under it. Matches can overlap.
+    pub fn find_pattern<P, F>(&self, pattern: &Grid<P>, mut matches: F) -> Vec<(usize, usize)>
+    where
+        F: FnMut(&P, &T) -> bool,
+    {
+        let mut found = Vec::new();
+        if pattern.width > self.width || pattern.height > self.height {
+            return found;
+        }
+        for y in 0..=self.height - pattern.height {
+            for x in 0..=self.width - pattern.width {
+                if pattern
+                    .iter()
+                    .all(|((px, py), p)| matches(p, &self[(x + px, y + py)]))
+                {
+                    found.push((x, y));
+                }
+            }
+        }
+        found
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        self.get(x, y)
+            .unwrap_or_else(|| panic!("({}, {}) is outside the grid", x, y))
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        self.get_mut(x, y)
+            .unwrap_or_else(|| panic!("({}, {}) is outside the grid", x, y))
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Grid<char> {
+        Grid::parse(0, "ab\ncd\nef", Some).unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let grid = sample();
+        assert_eq!((2, 3), (grid.width(), grid.height()));
+        assert_eq!(Some(&'d'), grid.get(1, 1));
+        assert_eq!(None, grid.get(2, 0));
+        assert_eq!("ab\ncd\nef", grid.to_string());
+
+        let error = Grid::parse(0, "#.\n#x", |c| "#.".find(c)).unwrap_err();
+        assert_eq!((2, 2), (error.line, error.column));
+        let error = Grid::parse(0, "ab\nabc", Some).unwrap_err();
+        assert_eq!("abc", error.text);
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = sample();
+        assert_eq!(
+            vec![(1, 0), (0, 1)],
+            grid.neighbours4(0, 0).collect::<Vec<_>>()
+        );
+        assert_eq!(5, grid.neighbours8(0, 1).count());
+        assert_eq!(8, Grid::new(3, 3, 0).neighbours8(1, 1).count());
+        assert_eq!(
+            vec![(0, 1), (0, 2)],
+            grid.ray(0, 0, 0, 1).collect::<Vec<_>>()
+        );
+        assert_eq!(vec![(1, 1)], grid.ray(0, 0, 1, 1).collect::<Vec<_>>());
+        assert_eq!(&'f', grid.get_wrapped(-1, 5));
+    }
+
+    #[test]
+    fn transforms() {
+        let grid = sample();
+        assert_eq!("eca\nfdb", grid.rotate().to_string());
+        assert_eq!("ba\ndc\nfe", grid.flip().to_string());
+        assert_eq!(grid, grid.rotate().rotate().rotate().rotate());
+
+        let orientations = grid.orientations();
+        assert_eq!(8, orientations.len());
+        assert_eq!(grid.flip().rotate(), orientations[5]);
+        assert!((1..8).all(|i| !orientations[..i].contains(&orientations[i])));
+
+        assert_eq!(vec!['b', 'd', 'f'], grid.column(1));
+        assert_eq!(vec!['c', 'd'], grid.row(1));
+        assert_eq!("d\nf", grid.crop(1, 1, 1, 2).to_string());
+
+        let mut grid = grid.map(|c| c.to_ascii_uppercase());
+        grid.set(0, 2, 'x');
+        assert_eq!("AB\nCD\nxF", grid.to_string());
+    }
+
+    #[test]
+    fn pattern() {
+        let grid = Grid::parse(0, "#.#.\n.#.#\n#.#.", |c| Some(c == '#')).unwrap();
+        let pattern = Grid::parse(0, "#.\n.#", |c| Some(c == '#')).unwrap();
+        let found = grid.find_pattern(&pattern, |p, g| !*p || *g);
+        assert_eq!(vec![(0, 0), (2, 0), (1, 1)], found);
+        assert!(pattern.find_pattern(&grid, |p, g| p == g).is_empty());
+    }
+}
@@ -24,7 +24,7 @@ pub mod d15; // elf memory game
 pub mod d16; // ticket scan
 pub mod d17; // Conway 3d and 4d
 pub mod d18; // RPN switchup
-// pub mod d19; // recursive rules
+pub mod d19; // recursive rules
 pub mod d20; // image assembly
 pub mod d21; // allergens
 pub mod d22; // Combat!
@@ -33,4 +33,17 @@ pub mod d23_2; // Cups p2
 pub mod d24; // Conway 2d Hex
 pub mod d25; // Encryption decode
 
+pub mod audit; // day 2 password audits against policy files
+pub mod automaton; // cellular automata for days 11, 17 and 24
+pub mod bench; // timings and JSON/CSV reports
+pub mod csv; // CSV fields for the reports
+pub mod error; // ParseError for the fallible generators
+pub mod fixture; // known answer manifests
+pub mod generate; // random inputs with known answers
+pub mod grid; // 2d grids of cells
+pub mod render; // frame export for the simulation days
+pub mod runner; // days run concurrently with timeouts
+pub mod solution; // Solution trait and day registry
+pub mod subset_sum; // counting and listing day 1 subsets with a given sum
+
 aoc_lib! { year = 2020 }
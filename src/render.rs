@@ -0,0 +1,284 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::grid::Grid;
+use crate::{d11, d12, d17, d24};
+
+// Frame by frame export for the simulation days. Every day draws each step as text and as an
+// image, which can be written out as .txt, .ppm or .png files numbered in order.
+//
+// The PNG encoder doesn't compress, it wraps the pixels in stored deflate blocks, so all it
+// needs is the crc32 and adler32 checksums.
+
+pub type Rgb = [u8; 3];
+
+pub const BACKGROUND: Rgb = [24, 24, 32];
+
+pub struct Frame {
+    pub text: String,
+    pub image: Grid<Rgb>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Ppm,
+    Png,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Text => "txt",
+            Format::Ppm => "ppm",
+            Format::Png => "png",
+        }
+    }
+
+    pub fn encode(&self, frame: &Frame) -> Vec<u8> {
+        match self {
+            Format::Text => format!("{}\n", frame.text).into_bytes(),
+            Format::Ppm => ppm(&frame.image),
+            Format::Png => png(&frame.image),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "txt" | "text" => Ok(Format::Text),
+            "ppm" => Ok(Format::Ppm),
+            "png" => Ok(Format::Png),
+            _ => Err(format!("unknown format {}, expected txt, ppm or png", s)),
+        }
+    }
+}
+
+// every step of the day's simulation, starting with the initial state
+pub fn frames(day: u32, part: u32, input: &str) -> Result<Vec<Frame>, String> {
+    if part != 1 && part != 2 {
+        return Err(format!("day {} has no part {}", day, part));
+    }
+    let frames = match day {
+        11 => d11::try_parse_input(input).map(|tiles| d11::frames(&tiles, part)),
+        12 => d12::try_parse_input(input).map(|route| d12::frames(&route, part)),
+        17 => d17::try_parse_input(input).map(|seed| d17::frames(&seed, part)),
+        24 => d24::try_parse_input(input).map(|tiles| d24::frames(&tiles, part)),
+        _ => return Err(format!("day {} has no simulation to render", day)),
+    };
+    frames.map_err(|e| e.to_string())
+}
+
+// <dir>/<prefix>_0000.<extension> and so on
+pub fn write_frames(dir: &Path, prefix: &str, frames: &[Frame], format: Format) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for (i, frame) in frames.iter().enumerate() {
+        let path = dir.join(format!("{}_{:04}.{}", prefix, i, format.extension()));
+        fs::write(path, format.encode(frame))?;
+    }
+    Ok(())
+}
+
+// each cell as a square of scale by scale pixels
+pub fn cells_image<T, F>(grid: &Grid<T>, scale: usize, colour: F) -> Grid<Rgb>
+where
+    F: Fn(&T) -> Rgb,
+{
+    let mut image = Grid::new(grid.width() * scale, grid.height() * scale, BACKGROUND);
+    for ((x, y), cell) in grid.iter() {
+        let pixel = colour(cell);
+        for dy in 0..scale {
+            for dx in 0..scale {
+                image.set(x * scale + dx, y * scale + dy, pixel);
+            }
+        }
+    }
+    image
+}
+
+// copies part into the image with its top left corner at (x, y)
+pub fn paste<T: Clone>(image: &mut Grid<T>, part: &Grid<T>, x: usize, y: usize) {
+    for ((px, py), cell) in part.iter() {
+        image.set(x + px, y + py, cell.clone());
+    }
+}
+
+// Bresenham's line, both ends included
+pub fn draw_line<T: Clone>(grid: &mut Grid<T>, from: (usize, usize), to: (usize, usize), value: T) {
+    let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+    let (x1, y1) = (to.0 as isize, to.1 as isize);
+    let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+    let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+    let mut error = dx + dy;
+    loop {
+        grid.set(x as usize, y as usize, value.clone());
+        if x == x1 && y == y1 {
+            return;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+}
+
+// binary P6
+pub fn ppm(image: &Grid<Rgb>) -> Vec<u8> {
+    let mut out = format!("P6\n{} {}\n255\n", image.width(), image.height()).into_bytes();
+    for (_pos, pixel) in image.iter() {
+        out.extend_from_slice(pixel);
+    }
+    out
+}
+
+pub fn png(image: &Grid<Rgb>) -> Vec<u8> {
+    // every row starts with its filter type, 0 for none
+    let mut raw = Vec::with_capacity((image.width() * 3 + 1) * image.height());
+    for row in image.rows() {
+        raw.push(0);
+        for pixel in row {
+            raw.extend_from_slice(pixel);
+        }
+    }
+
+    // zlib header, stored deflate blocks of at most 65535 bytes, adler32 of the data
+    let mut zlib = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = if raw.is_empty() {
+        vec![&[]]
+    } else {
+        raw.chunks(65535).collect()
+    };
+    for (i, block) in blocks.iter().enumerate() {
+        zlib.push((i + 1 == blocks.len()) as u8);
+        let len = block.len() as u16;
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    // 8 bits per channel, RGB, no interlacing
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(image.width() as u32).to_be_bytes());
+    header.extend_from_slice(&(image.height() as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+    png_chunk(&mut out, b"IHDR", &header);
+    png_chunk(&mut out, b"IDAT", &zlib);
+    png_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1_u32, 0_u32);
+    for byte in bytes {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        crate::fixture::read_input(&path).unwrap()
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(0xae42_6082, crc32(b"IEND"));
+        assert_eq!(0x11e6_0398, adler32(b"Wikipedia"));
+    }
+
+    #[test]
+    fn encoders() {
+        let mut image = Grid::new(3, 2, BACKGROUND);
+        draw_line(&mut image, (2, 1), (0, 0), [255, 0, 0]);
+        assert_eq!([255, 0, 0], image[(1, 0)]);
+        assert_eq!(BACKGROUND, image[(0, 1)]);
+
+        let ppm = ppm(&image);
+        assert!(ppm.starts_with(b"P6\n3 2\n255\n"));
+        assert_eq!(11 + 3 * 2 * 3, ppm.len());
+
+        // signature, IHDR, one stored block of two 10 byte rows, IEND
+        let png = png(&image);
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x03\0\0\0\x02"));
+        assert_eq!(8 + 25 + (12 + 2 + 5 + 20 + 4) + 12, png.len());
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+    }
+
+    #[test]
+    fn simulations() {
+        let seats = frames(11, 1, &fixture("day11.txt")).unwrap();
+        let last = &seats.last().unwrap().text;
+        assert_eq!(37, last.chars().filter(|c| *c == '#').count());
+
+        let cubes = frames(17, 1, &fixture("day17.txt")).unwrap();
+        assert_eq!(7, cubes.len());
+        let active = |frame: &Frame| frame.text.chars().filter(|c| *c == '#').count();
+        assert_eq!(5, active(&cubes[0]));
+        assert_eq!(112, active(&cubes[6]));
+        assert!(cubes[0].text.starts_with("z=-4\n"));
+
+        let route = frames(12, 2, &fixture("day12.txt")).unwrap();
+        assert_eq!(6, route.len());
+        assert!(route[5]
+            .text
+            .starts_with("ship at (214, -72), waypoint (4, -10)"));
+
+        let input = fixture("day24.txt");
+        let tiles = d24::parse_input(&input);
+        let flips = frames(24, 1, &input).unwrap();
+        assert_eq!(tiles.len() + 1, flips.len());
+        assert_eq!(0, active(&flips[0]));
+        assert_eq!(d24::part1(&tiles), active(flips.last().unwrap()));
+        let days = frames(24, 2, &input).unwrap();
+        assert_eq!(101, days.len());
+        assert_eq!(d24::part1(&tiles), active(&days[0]));
+        assert_eq!(d24::part2(&tiles), active(days.last().unwrap()));
+
+        assert!(frames(5, 1, "").is_err());
+        assert!(frames(11, 3, "").is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::solution::{self, Error, Timed};
+
+// Runs many day/part tasks at once on a fixed number of worker threads and collects how each
+// of them went. A worker hands its task to a thread of its own and waits for it at most
+// `timeout`. Threads can't be killed, so a timeout doesn't cancel the task: it is reported as
+// timed out and keeps running in the background until it finishes or the process exits. It
+// still holds its place in the pool meanwhile, so there are never more than `threads` tasks
+// running and later tasks wait for a place. Tasks that never finish keep theirs for good.
+
+// day 23 part 2 keeps its million cups on the stack, more than the default for a new thread
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct Task {
+    pub day: u32,
+    pub part: u32,
+    // shared by the parts of a day
+    pub input: Arc<str>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    Solved(Timed),
+    Failed(Error),
+    Panicked(String),
+    TimedOut,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub day: u32,
+    pub part: u32,
+    pub status: Status,
+    // from the start of the task until it finished or was given up on
+    pub wall: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    pub threads: usize,
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            timeout: None,
+        }
+    }
+}
+
+// the outcomes are in the same order as the tasks
+pub fn run(tasks: Vec<Task>, config: Config) -> Vec<Outcome> {
+    run_with(tasks, config, solution::solve_timed)
+}
+
+fn run_with<F>(tasks: Vec<Task>, config: Config, solve: F) -> Vec<Outcome>
+where
+    F: Fn(u32, u32, &str) -> Result<Timed, Error> + Send + Sync + 'static,
+{
+    let count = tasks.len();
+    let queue = Arc::new(Mutex::new(
+        tasks.into_iter().enumerate().collect::<VecDeque<_>>(),
+    ));
+    let solve = Arc::new(solve);
+    let threads = config.threads.clamp(1, count.max(1));
+    let places = Arc::new(Places::new(threads));
+    let (sender, receiver) = mpsc::channel();
+
+    let workers: Vec<_> = (0..threads)
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            let solve = Arc::clone(&solve);
+            let places = Arc::clone(&places);
+            let sender = sender.clone();
+            thread::spawn(move || loop {
+                places.take();
+                let next = queue.lock().unwrap().pop_front();
+                let (index, task) = match next {
+                    Some(next) => next,
+                    None => {
+                        places.give_back();
+                        return;
+                    }
+                };
+                let outcome = attempt(task, config.timeout, &solve, &places);
+                sender.send((index, outcome)).unwrap();
+            })
+        })
+        .collect();
+    drop(sender);
+
+    let mut outcomes: Vec<Option<Outcome>> = vec![None; count];
+    for (index, outcome) in receiver {
+        outcomes[index] = Some(outcome);
+    }
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    outcomes.into_iter().map(Option::unwrap).collect()
+}
+
+// the running task threads, abandoned ones included, against how many there may be
+struct Places {
+    running: Mutex<usize>,
+    freed: Condvar,
+    limit: usize,
+}
+
+impl Places {
+    fn new(limit: usize) -> Self {
+        Places {
+            running: Mutex::new(0),
+            freed: Condvar::new(),
+            limit,
+        }
+    }
+
+    // waits until there is a free place
+    fn take(&self) {
+        let mut running = self.running.lock().unwrap();
+        while *running >= self.limit {
+            running = self.freed.wait(running).unwrap();
+        }
+        *running += 1;
+    }
+
+    fn give_back(&self) {
+        *self.running.lock().unwrap() -= 1;
+        self.freed.notify_one();
+    }
+}
+
+// runs the task on a thread of its own in a place already taken, which the thread gives back
+// when the task finishes, even after a timeout
+fn attempt<F>(
+    task: Task,
+    timeout: Option<Duration>,
+    solve: &Arc<F>,
+    places: &Arc<Places>,
+) -> Outcome
+where
+    F: Fn(u32, u32, &str) -> Result<Timed, Error> + Send + Sync + 'static,
+{
+    let (day, part) = (task.day, task.part);
+    let start = Instant::now();
+    let (sender, receiver) = mpsc::channel();
+    let (solve, places) = (Arc::clone(solve), Arc::clone(places));
+    let builder = thread::Builder::new()
+        .name(format!("day{}_part{}", day, part))
+        .stack_size(STACK_SIZE);
+    let spawned = builder.spawn(move || {
+        let result =
+            panic::catch_unwind(AssertUnwindSafe(|| solve(task.day, task.part, &task.input)));
+        places.give_back();
+        // nobody is listening any more after a timeout
+        let _ = sender.send(result);
+    });
+    spawned.expect("can't spawn a thread for the task");
+
+    let received = match timeout {
+        Some(timeout) => receiver.recv_timeout(timeout).ok(),
+        None => receiver.recv().ok(),
+    };
+    let status = match received {
+        Some(Ok(Ok(timed))) => Status::Solved(timed),
+        Some(Ok(Err(e))) => Status::Failed(e),
+        Some(Err(payload)) => Status::Panicked(solution::panic_message(payload.as_ref())),
+        None => Status::TimedOut,
+    };
+    Outcome {
+        day,
+        part,
+        status,
+        wall: start.elapsed(),
+    }
+}
+
+// a table with a line per task and a count of each status at the end
+pub fn summary(outcomes: &[Outcome], wall: Duration) -> String {
+    let mut out = format!(
+        "{:>3} {:>4} {:<20} {:>12} {:>12} {}\n",
+        "day", "part", "answer", "generator", "solver", "status"
+    );
+    let (mut solved, mut failed, mut panicked, mut timed_out) = (0, 0, 0, 0);
+    for outcome in outcomes {
+        let (answer, parse, solve, status) = match &outcome.status {
+            Status::Solved(timed) => {
+                solved += 1;
+                (
+                    timed.answer.to_string(),
+                    format!("{:.1?}", timed.parse),
+                    format!("{:.1?}", timed.solve),
+                    "ok".to_string(),
+                )
+            }
+            Status::Failed(e) => {
+                failed += 1;
+                (
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    format!("error: {}", e),
+                )
+            }
+            Status::Panicked(message) => {
+                panicked += 1;
+                let status = format!("panicked: {}", message);
+                (String::new(), String::new(), String::new(), status)
+            }
+            Status::TimedOut => {
+                timed_out += 1;
+                let status = format!("timed out after {:.1?}", outcome.wall);
+                (String::new(), String::new(), String::new(), status)
+            }
+        };
+        writeln!(
+            out,
+            "{:>3} {:>4} {:<20} {:>12} {:>12} {}",
+            outcome.day, outcome.part, answer, parse, solve, status
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "{} solved, {} failed, {} panicked, {} timed out in {:.1?}",
+        solved, failed, panicked, timed_out, wall
+    )
+    .unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::Answer;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn task(day: u32, part: u32, input: &str) -> Task {
+        Task {
+            day,
+            part,
+            input: Arc::from(input),
+        }
+    }
+
+    #[test]
+    fn statuses() {
+        let tasks = vec![
+            task(1, 1, "1721\n299"),
+            task(1, 2, "nope"),
+            task(2, 1, ""),
+            task(3, 1, ""),
+            task(1, 3, ""),
+        ];
+        let config = Config {
+            threads: 2,
+            timeout: Some(Duration::from_millis(200)),
+        };
+        let outcomes = run_with(tasks, config, |day, part, input| match day {
+            2 => panic!("day {} exploded", day),
+            3 => {
+                thread::sleep(Duration::from_secs(5));
+                unreachable!()
+            }
+            _ => solution::solve_timed(day, part, input),
+        });
+
+        let days: Vec<_> = outcomes.iter().map(|o| (o.day, o.part)).collect();
+        assert_eq!(vec![(1, 1), (1, 2), (2, 1), (3, 1), (1, 3)], days);
+        assert!(matches!(
+            &outcomes[0].status,
+            Status::Solved(timed) if timed.answer == Answer::Int(514579)
+        ));
+        assert!(matches!(
+            outcomes[1].status,
+            Status::Failed(Error::Parse(_))
+        ));
+        assert_eq!(
+            Status::Panicked("day 2 exploded".to_string()),
+            outcomes[2].status
+        );
+        assert_eq!(Status::TimedOut, outcomes[3].status);
+        assert_eq!(
+            Status::Failed(Error::UnknownPart { day: 1, part: 3 }),
+            outcomes[4].status
+        );
+
+        let summary = summary(&outcomes, Duration::from_secs(1));
+        assert!(summary.contains("514579"));
+        assert!(summary.contains("panicked: day 2 exploded"));
+        assert!(summary.ends_with("1 solved, 2 failed, 1 panicked, 1 timed out in 1.0s\n"));
+    }
+
+    #[test]
+    fn timed_out_tasks_keep_their_place() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let tasks = (0..5).map(|_| task(3, 1, "")).collect();
+        let config = Config {
+            threads: 2,
+            timeout: Some(Duration::from_millis(20)),
+        };
+        let (counter, highest) = (Arc::clone(&running), Arc::clone(&most));
+        let outcomes = run_with(tasks, config, move |day, _part, _input| {
+            highest.fetch_max(counter.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(100));
+            counter.fetch_sub(1, Ordering::SeqCst);
+            Err(Error::UnknownDay(day))
+        });
+
+        assert!(outcomes.iter().all(|o| o.status == Status::TimedOut));
+        assert_eq!(2, most.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn no_tasks() {
+        assert!(run(Vec::new(), Config::default()).is_empty());
+    }
+}
@@ -0,0 +1,376 @@
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+use std::time::{Duration, Instant};
+
+use crate::error::ParseError;
+use crate::*;
+
+// A library entry point for every day that doesn't go through the aoc-runner macros.
+// Each day is a unit struct implementing Solution on top of its module's existing
+// parse_input/part1/part2 functions, and `solve` looks them up by number.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Int(i64),
+    Str(String),
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Int(n) => f.pad(&n.to_string()),
+            Answer::Str(s) => f.pad(s),
+        }
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(n: i64) -> Self {
+        Answer::Int(n)
+    }
+}
+
+impl From<i32> for Answer {
+    fn from(n: i32) -> Self {
+        Answer::Int(n as i64)
+    }
+}
+
+// anything too large for an i64 keeps its digits as a string
+impl From<u64> for Answer {
+    fn from(n: u64) -> Self {
+        match i64::try_from(n) {
+            Ok(n) => Answer::Int(n),
+            Err(_) => Answer::Str(n.to_string()),
+        }
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Self {
+        Answer::from(n as u64)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Str(s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    UnknownDay(u32),
+    UnknownPart { day: u32, part: u32 },
+    Parse(ParseError),
+    // the input parsed but the solver gave up on it, with the reason it gave
+    NoAnswer { day: u32, part: u32, reason: String },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownDay(day) => write!(f, "day {} is not solved", day),
+            Error::UnknownPart { day, part } => write!(f, "day {} has no part {}", day, part),
+            Error::Parse(e) => write!(f, "{}", e),
+            Error::NoAnswer { day, part, reason } => {
+                write!(f, "day {} part {} has no answer: {}", day, part, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
+pub trait Solution {
+    type Input;
+
+    // day 25 only has a single puzzle
+    const PARTS: u32 = 2;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError>;
+
+    fn part1(input: &Self::Input) -> Answer;
+
+    // None on a day without a second part
+    fn part2(_input: &Self::Input) -> Option<Answer> {
+        None
+    }
+}
+
+macro_rules! solution {
+    ($name:ident, $day:ident, $input:ty) => {
+        solution!(
+            $name,
+            $input,
+            $day::try_parse_input,
+            $day::part1,
+            $day::part2
+        );
+    };
+    ($name:ident, $input:ty, $parse:path, $part1:path, $part2:path) => {
+        pub struct $name;
+
+        impl Solution for $name {
+            type Input = $input;
+
+            fn parse(input: &str) -> Result<Self::Input, ParseError> {
+                $parse(input)
+            }
+
+            fn part1(input: &Self::Input) -> Answer {
+                $part1(input).into()
+            }
+
+            fn part2(input: &Self::Input) -> Option<Answer> {
+                Some($part2(input).into())
+            }
+        }
+    };
+}
+
+// days 17 and 23 solve straight from the raw input, once it is known to be valid
+fn d17_input(input: &str) -> Result<String, ParseError> {
+    d17::try_parse_input(input).map(|_| input.to_string())
+}
+
+fn d23_input(input: &str) -> Result<String, ParseError> {
+    d23_1::try_parse_input(input).map(|_| input.to_string())
+}
+
+solution!(Day01, d01, Vec<i32>);
+solution!(Day02, d02, Vec<d02::PolicyPass>);
+solution!(Day03, d03, d03::Forest);
+solution!(Day04, d04, Vec<d04::Passport>);
+solution!(Day05, d05, Vec<d05::SeatId>);
+solution!(Day06, d06, Vec<d06::Group>);
+solution!(Day07, d07, d07::RuleSet);
+solution!(Day08, d08, Vec<d08::Instruction>);
+solution!(Day09, d09, Vec<usize>);
+solution!(Day10, d10, Vec<usize>);
+solution!(Day11, d11, crate::grid::Grid<d11::Tile>);
+solution!(Day12, d12, Vec<d12::Instruction>);
+solution!(Day13, d13, d13::D13Input);
+solution!(Day14, d14, Vec<d14::Instruction>);
+solution!(Day15, d15, Vec<usize>);
+solution!(Day16, d16, d16::D16Input);
+solution!(Day17, String, d17_input, d17::part1, d17::part2);
+solution!(Day18, d18, Vec<String>);
+solution!(Day19, d19, d19::D19Input);
+solution!(Day20, d20, Vec<d20::Tile>);
+solution!(Day21, d21, Vec<d21::Food>);
+solution!(Day22, d22, d22::Decks);
+solution!(Day23, String, d23_input, d23_1::part1, d23_2::part2);
+solution!(Day24, d24, Vec<Vec<d24::Dir>>);
+
+pub struct Day25;
+
+impl Solution for Day25 {
+    type Input = Vec<usize>;
+
+    const PARTS: u32 = 1;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        d25::try_parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        d25::part1(input).into()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timed {
+    pub answer: Answer,
+    pub parse: Duration,
+    pub solve: Duration,
+}
+
+thread_local! {
+    // set while a solver runs, its panics are returned rather than printed
+    static SOLVING: Cell<bool> = const { Cell::new(false) };
+}
+
+static QUIET_HOOK: Once = Once::new();
+
+// The solvers panic when there is no answer, such as day 1 without a pair adding up to 2020.
+// That is caught here and returned as NoAnswer, without the panic message on stderr. Panics
+// anywhere else still go to the hook that was there before.
+fn run<S: Solution>(day: u32, part: u32, input: &str) -> Result<Timed, Error> {
+    if part == 0 || part > S::PARTS {
+        return Err(Error::UnknownPart { day, part });
+    }
+    QUIET_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !SOLVING.with(Cell::get) {
+                previous(info);
+            }
+        }));
+    });
+
+    let solving = SOLVING.with(|solving| solving.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| run_unguarded::<S>(day, part, input)));
+    SOLVING.with(|s| s.set(solving));
+    result.unwrap_or_else(|payload| {
+        Err(Error::NoAnswer {
+            day,
+            part,
+            reason: panic_message(payload.as_ref()),
+        })
+    })
+}
+
+fn run_unguarded<S: Solution>(day: u32, part: u32, input: &str) -> Result<Timed, Error> {
+    let start = Instant::now();
+    let parsed = S::parse(input)?;
+    let parse = start.elapsed();
+
+    let start = Instant::now();
+    let answer = match part {
+        1 => S::part1(&parsed),
+        _ => S::part2(&parsed).ok_or(Error::UnknownPart { day, part })?,
+    };
+
+    Ok(Timed {
+        answer,
+        parse,
+        solve: start.elapsed(),
+    })
+}
+
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+// days with a solution, in order
+pub const DAYS: [u32; 25] = [
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+];
+
+pub fn parts(day: u32) -> u32 {
+    match day {
+        25 => Day25::PARTS,
+        _ if DAYS.contains(&day) => 2,
+        _ => 0,
+    }
+}
+
+pub fn solve(day: u32, part: u32, input: &str) -> Result<Answer, Error> {
+    solve_timed(day, part, input).map(|timed| timed.answer)
+}
+
+// like solve, but also reports how long the generator and the solver took
+pub fn solve_timed(day: u32, part: u32, input: &str) -> Result<Timed, Error> {
+    match day {
+        1 => run::<Day01>(day, part, input),
+        2 => run::<Day02>(day, part, input),
+        3 => run::<Day03>(day, part, input),
+        4 => run::<Day04>(day, part, input),
+        5 => run::<Day05>(day, part, input),
+        6 => run::<Day06>(day, part, input),
+        7 => run::<Day07>(day, part, input),
+        8 => run::<Day08>(day, part, input),
+        9 => run::<Day09>(day, part, input),
+        10 => run::<Day10>(day, part, input),
+        11 => run::<Day11>(day, part, input),
+        12 => run::<Day12>(day, part, input),
+        13 => run::<Day13>(day, part, input),
+        14 => run::<Day14>(day, part, input),
+        15 => run::<Day15>(day, part, input),
+        16 => run::<Day16>(day, part, input),
+        17 => run::<Day17>(day, part, input),
+        18 => run::<Day18>(day, part, input),
+        19 => run::<Day19>(day, part, input),
+        20 => run::<Day20>(day, part, input),
+        21 => run::<Day21>(day, part, input),
+        22 => run::<Day22>(day, part, input),
+        23 => run::<Day23>(day, part, input),
+        24 => run::<Day24>(day, part, input),
+        25 => run::<Day25>(day, part, input),
+        _ => Err(Error::UnknownDay(day)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solve_by_number() {
+        assert_eq!(
+            Ok(Answer::Int(514579)),
+            solve(1, 1, "1721\n979\n366\n299\n675\n1456")
+        );
+        assert_eq!(Ok(Answer::Int(5)), solve(8, 1, "nop +0\nacc +5\njmp -2"));
+        assert_eq!(Ok(Answer::Int(14897079)), solve(25, 1, "5764801\n17807724"));
+        assert_eq!(
+            Err(Error::UnknownPart { day: 25, part: 2 }),
+            solve(25, 2, "5764801\n17807724")
+        );
+        assert_eq!(Err(Error::UnknownPart { day: 1, part: 3 }), solve(1, 3, ""));
+        assert_eq!(Err(Error::UnknownDay(0)), solve(0, 1, ""));
+        assert_eq!(Err(Error::UnknownDay(26)), solve(26, 1, ""));
+        assert!(matches!(
+            solve(8, 1, "nop +0\nhcf +0"),
+            Err(Error::Parse(_))
+        ));
+        assert_eq!(
+            Err(Error::NoAnswer {
+                day: 1,
+                part: 1,
+                reason: "not found".to_string()
+            }),
+            solve(1, 1, "1\n2")
+        );
+    }
+
+    #[test]
+    fn missing_part2() {
+        // claims two parts but only has the first
+        struct Half;
+
+        impl Solution for Half {
+            type Input = ();
+
+            fn parse(_input: &str) -> Result<Self::Input, ParseError> {
+                Ok(())
+            }
+
+            fn part1(_input: &Self::Input) -> Answer {
+                Answer::Int(1)
+            }
+        }
+
+        assert_eq!(Answer::Int(1), run::<Half>(26, 1, "").unwrap().answer);
+        assert_eq!(
+            Err(Error::UnknownPart { day: 26, part: 2 }),
+            run::<Half>(26, 2, "")
+        );
+    }
+
+    #[test]
+    fn answers() {
+        assert_eq!("-3", Answer::from(-3_i64).to_string());
+        assert_eq!(Answer::Int(7), Answer::from(7_usize));
+        assert_eq!(
+            Answer::Str("18446744073709551615".to_string()),
+            Answer::from(u64::MAX)
+        );
+    }
+}
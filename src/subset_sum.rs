@@ -0,0 +1,417 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+// Subsets of the day 1 entries, as returned by d01::parse_input, that add up to a target.
+//
+// Both counting and listing work over the range of sums a subset can have: from the sum of
+// the negative entries to the sum of the positive ones, or just 0..=target when there are no
+// negative entries. When that range is too wide for a table, as with large values, they keep
+// the sums that occur in hash maps instead. Subsets are sets of entries, so equal values on
+// different lines are different entries: [5, 5] has two subsets that sum to 5. The empty
+// subset sums to 0.
+
+// An unsigned integer of any size, as the number of subsets can reach 2^n.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Count {
+    // base 2^32, least significant first, no trailing zeros
+    limbs: Vec<u32>,
+}
+
+impl Count {
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn add(&mut self, other: &Count) {
+        if self.limbs.len() < other.limbs.len() {
+            self.limbs.resize(other.limbs.len(), 0);
+        }
+        let mut carry = 0_u64;
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            let sum = *limb as u64 + *other.limbs.get(i).unwrap_or(&0) as u64 + carry;
+            *limb = sum as u32;
+            carry = sum >> 32;
+            if carry == 0 && i >= other.limbs.len() {
+                break;
+            }
+        }
+        if carry > 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+}
+
+impl From<u64> for Count {
+    fn from(n: u64) -> Self {
+        let limbs = match (n as u32, (n >> 32) as u32) {
+            (0, 0) => vec![],
+            (low, 0) => vec![low],
+            (low, high) => vec![low, high],
+        };
+        Count { limbs }
+    }
+}
+
+impl Display for Count {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // nine decimal digits at a time, least significant first
+        let mut rest = self.limbs.clone();
+        let mut chunks = Vec::new();
+        while !rest.is_empty() {
+            let mut remainder = 0_u64;
+            for limb in rest.iter_mut().rev() {
+                let value = (remainder << 32) | *limb as u64;
+                *limb = (value / 1_000_000_000) as u32;
+                remainder = value % 1_000_000_000;
+            }
+            while rest.last() == Some(&0) {
+                rest.pop();
+            }
+            chunks.push(remainder);
+        }
+
+        let mut digits = chunks.pop().unwrap_or(0).to_string();
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{:09}", chunk));
+        }
+        f.pad(&digits)
+    }
+}
+
+// above these sizes the tables only hold the sums that occur
+const DENSE_COUNTS: usize = 1 << 20;
+const DENSE_BITS: usize = 1 << 28;
+
+// the sums subsets can have, as offsets from the smallest
+struct Sums {
+    low: i64,
+    high: i64,
+}
+
+impl Sums {
+    fn new(entries: &[i32], target: i64) -> Self {
+        let low: i64 = entries.iter().map(|e| i64::from(*e).min(0)).sum();
+        let high: i64 = entries.iter().map(|e| i64::from(*e).max(0)).sum();
+        // larger sums can't come back down to the target
+        let high = if low == 0 { high.min(target) } else { high };
+        Sums { low, high }
+    }
+
+    fn width(&self) -> usize {
+        (self.high - self.low + 1).max(0) as usize
+    }
+
+    fn offset(&self, sum: i64) -> Option<usize> {
+        if sum >= self.low && sum <= self.high {
+            Some((sum - self.low) as usize)
+        } else {
+            None
+        }
+    }
+}
+
+// Whether the entries from i on can still take a sum to the target, by the range of sums they
+// can add. The sparse tables drop every sum that can't.
+struct Remaining {
+    target: i64,
+    // the sums of the negative and of the positive entries from i on
+    ranges: Vec<(i64, i64)>,
+}
+
+impl Remaining {
+    fn new(entries: &[i32], target: i64) -> Self {
+        let mut ranges = vec![(0, 0); entries.len() + 1];
+        for (i, entry) in entries.iter().enumerate().rev() {
+            let (low, high) = ranges[i + 1];
+            let entry = i64::from(*entry);
+            ranges[i] = (low + entry.min(0), high + entry.max(0));
+        }
+        Remaining { target, ranges }
+    }
+
+    fn allows(&self, i: usize, sum: i64) -> bool {
+        let (low, high) = self.ranges[i];
+        (low..=high).contains(&(self.target - sum))
+    }
+}
+
+pub fn count(entries: &[i32], target: i64) -> Count {
+    let sums = Sums::new(entries, target);
+    let end = match sums.offset(target) {
+        Some(end) => end,
+        None => return Count::default(),
+    };
+    if sums.width() > DENSE_COUNTS {
+        return sparse_count(entries, target);
+    }
+
+    // counts[s] is the number of subsets of the entries so far with sum s
+    let mut counts = vec![Count::default(); sums.width()];
+    counts[sums.offset(0).unwrap()] = Count::from(1);
+    for entry in entries {
+        let shift = *entry as isize;
+        let offsets: Box<dyn Iterator<Item = usize>> = if shift > 0 {
+            Box::new((0..counts.len()).rev())
+        } else {
+            Box::new(0..counts.len())
+        };
+        // each sum is read before it is added to, every subset takes the entry at most once
+        for from in offsets {
+            let to = from as isize + shift;
+            if to < 0 || to as usize >= counts.len() || counts[from].is_zero() {
+                continue;
+            }
+            let ways = counts[from].clone();
+            counts[to as usize].add(&ways);
+        }
+    }
+    counts.swap_remove(end)
+}
+
+fn sparse_count(entries: &[i32], target: i64) -> Count {
+    let remaining = Remaining::new(entries, target);
+    let mut counts: HashMap<i64, Count> = HashMap::new();
+    counts.insert(0, Count::from(1));
+    for (i, entry) in entries.iter().enumerate() {
+        let mut next = counts.clone();
+        for (sum, ways) in &counts {
+            next.entry(sum + i64::from(*entry)).or_default().add(ways);
+        }
+        next.retain(|sum, _| remaining.allows(i + 1, *sum));
+        counts = next;
+    }
+    counts.remove(&target).unwrap_or_default()
+}
+
+// the sums of subsets of the first i entries, for every i
+enum Reachable {
+    Dense { low: i64, bits: Vec<Vec<u64>> },
+    Sparse(Vec<HashSet<i64>>),
+}
+
+impl Reachable {
+    fn new(entries: &[i32], target: i64) -> Self {
+        let sums = Sums::new(entries, target);
+        let width = sums.width();
+        if width.saturating_mul(entries.len() + 1) > DENSE_BITS {
+            return Reachable::sparse(entries, target);
+        }
+
+        let mut bits = vec![vec![0_u64; width.div_ceil(64)]];
+        if let Some(zero) = sums.offset(0) {
+            bits[0][zero / 64] |= 1 << (zero % 64);
+        }
+        for entry in entries {
+            let previous = bits.last().unwrap();
+            let mut next = previous.clone();
+            for (w, word) in previous.iter().enumerate() {
+                let mut set = *word;
+                while set != 0 {
+                    let from = w * 64 + set.trailing_zeros() as usize;
+                    set &= set - 1;
+                    let to = from as i64 + i64::from(*entry);
+                    if to >= 0 && (to as usize) < width {
+                        next[to as usize / 64] |= 1 << (to % 64);
+                    }
+                }
+            }
+            bits.push(next);
+        }
+        Reachable::Dense {
+            low: sums.low,
+            bits,
+        }
+    }
+
+    fn sparse(entries: &[i32], target: i64) -> Self {
+        let remaining = Remaining::new(entries, target);
+        let mut sets: Vec<HashSet<i64>> = vec![std::iter::once(0).collect()];
+        for (i, entry) in entries.iter().enumerate() {
+            let previous = sets.last().unwrap();
+            let next = previous
+                .iter()
+                .flat_map(|sum| vec![*sum, sum + i64::from(*entry)])
+                .filter(|sum| remaining.allows(i + 1, *sum))
+                .collect();
+            sets.push(next);
+        }
+        Reachable::Sparse(sets)
+    }
+
+    fn contains(&self, count: usize, sum: i64) -> bool {
+        match self {
+            Reachable::Dense { low, bits } => {
+                let offset = sum - low;
+                offset >= 0
+                    && (offset as usize) < bits[count].len() * 64
+                    && bits[count][offset as usize / 64] & (1 << (offset % 64)) != 0
+            }
+            Reachable::Sparse(sets) => sets[count].contains(&sum),
+        }
+    }
+}
+
+// Up to limit subsets that add up to target, each as its indices in increasing order. Those
+// with later entries come first.
+pub fn subsets(entries: &[i32], target: i64, limit: usize) -> Subsets<'_> {
+    let stack = if limit > 0 {
+        vec![(entries.len(), target, Vec::new())]
+    } else {
+        Vec::new()
+    };
+    Subsets {
+        entries,
+        reachable: Reachable::new(entries, target),
+        stack,
+        remaining: limit,
+    }
+}
+
+pub struct Subsets<'a> {
+    entries: &'a [i32],
+    reachable: Reachable,
+    // the entries still to decide on, the sum they have to make and the indices taken so far
+    stack: Vec<(usize, i64, Vec<usize>)>,
+    remaining: usize,
+}
+
+impl Iterator for Subsets<'_> {
+    type Item = Vec<usize>;
+
+    // only states that can still reach the target are pushed, so every path ends in a subset
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        while let Some((count, sum, taken)) = self.stack.pop() {
+            if !self.reachable.contains(count, sum) {
+                continue;
+            }
+            if count == 0 {
+                self.remaining -= 1;
+                let mut subset = taken;
+                subset.reverse();
+                return Some(subset);
+            }
+
+            let i = count - 1;
+            let with = sum - i64::from(self.entries[i]);
+            // pushed last so taking the entry is tried first
+            if self.reachable.contains(i, sum) {
+                self.stack.push((i, sum, taken.clone()));
+            }
+            if self.reachable.contains(i, with) {
+                let mut taken = taken;
+                taken.push(i);
+                self.stack.push((i, with, taken));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::d01::parse_input;
+
+    fn values(entries: &[i32], subset: &[usize]) -> Vec<i32> {
+        subset.iter().map(|i| entries[*i]).collect()
+    }
+
+    #[test]
+    fn counts() {
+        let entries = parse_input("1\n2\n3\n4\n5");
+        assert_eq!("3", count(&entries, 5).to_string());
+        assert_eq!("1", count(&entries, 0).to_string());
+        assert_eq!("1", count(&entries, 15).to_string());
+        assert_eq!("0", count(&entries, 16).to_string());
+        assert_eq!("0", count(&entries, -1).to_string());
+
+        // each 5 is an entry of its own
+        assert_eq!(Count::from(3), count(&[5, 5, 5], 10));
+        assert_eq!(Count::from(2), count(&[-1, 1, 2], 1));
+        assert_eq!(Count::from(2), count(&[-3, 3], 0));
+
+        assert_eq!(
+            "1606938044258990275541962092341162602522202993782792835301376",
+            count(&[0; 200], 0).to_string()
+        );
+        // 130 choose 65, computed with Pascal's triangle
+        let mut row = vec![1_u128];
+        for _ in 0..130 {
+            let mut next = vec![1_u128; row.len() + 1];
+            for k in 1..row.len() {
+                next[k] = row[k - 1] + row[k];
+            }
+            row = next;
+        }
+        assert_eq!(row[65].to_string(), count(&[1; 130], 65).to_string());
+    }
+
+    #[test]
+    fn large_values() {
+        let big = 2_000_000_000;
+        assert_eq!(Count::from(1), count(&[big], i64::from(big)));
+        assert_eq!(Count::from(0), count(&[big], 1));
+        assert_eq!(Count::from(2), count(&[big, big, 7], 2_000_000_007));
+        // the range of sums is 8e9 wide, there are only a few of them
+        let entries = vec![big, -big, 1_000_000_000, -1_000_000_000, 1_000_000_000];
+        assert_eq!(Count::from(7), count(&entries, 1_000_000_000));
+        assert_eq!(Count::from(7), count(&entries, 0));
+
+        let all: Vec<_> = subsets(&entries, 1_000_000_000, usize::MAX).collect();
+        assert_eq!(7, all.len());
+        for subset in &all {
+            let sum: i64 = subset.iter().map(|i| i64::from(entries[*i])).sum();
+            assert_eq!(1_000_000_000, sum);
+        }
+        assert_eq!(
+            vec![vec![0]],
+            subsets(&[big], i64::from(big), 5).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![vec![0, 1]],
+            subsets(&[i32::MAX, i32::MIN], -1, 5).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn big_counts() {
+        let mut n = Count::from(u64::MAX);
+        n.add(&Count::from(1));
+        assert_eq!("18446744073709551616", n.to_string());
+        n.add(&Count::from(1_000_000_000));
+        assert_eq!("18446744074709551616", n.to_string());
+        assert_eq!("0", Count::default().to_string());
+        assert_eq!("      1000", format!("{:>10}", Count::from(1000)));
+    }
+
+    #[test]
+    fn listing() {
+        let entries = parse_input("1721\n979\n366\n299\n675\n1456");
+        let all: Vec<_> = subsets(&entries, 2020, usize::MAX).collect();
+        assert_eq!(vec![vec![1, 2, 4], vec![0, 3]], all);
+
+        let entries = vec![3, 1, 4, 1, 5, 9, 2, 6];
+        let all: Vec<_> = subsets(&entries, 10, usize::MAX).collect();
+        assert_eq!(count(&entries, 10), Count::from(all.len() as u64));
+        for subset in &all {
+            assert!(subset.windows(2).all(|w| w[0] < w[1]));
+            assert_eq!(10, values(&entries, subset).iter().sum::<i32>());
+        }
+        // 1 + 9 once with each of the ones
+        assert!(all.contains(&vec![1, 5]) && all.contains(&vec![3, 5]));
+
+        assert_eq!(3, subsets(&entries, 10, 3).count());
+        assert_eq!(0, subsets(&entries, 10, 0).count());
+        assert_eq!(
+            vec![Vec::<usize>::new()],
+            subsets(&entries, 0, 5).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![vec![0, 2], vec![1]],
+            subsets(&[-1, 1, 2], 1, 5).collect::<Vec<_>>()
+        );
+    }
+}
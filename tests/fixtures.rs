@@ -0,0 +1,38 @@
+use std::path::{Path, PathBuf};
+
+use aoc_2020::fixture::Fixture;
+
+fn check(day: u32, part: u32, file: &str, expected: &str, line: usize) {
+    let input = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(file);
+    run(day, part, input, expected, line);
+}
+
+// Lines without an input file are for the real inputs, which aren't part of the repository.
+// Unused while the manifest has none.
+#[allow(dead_code)]
+fn check_input(day: u32, part: u32, expected: &str, line: usize) {
+    let input = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("input/2020/day{}.txt", day));
+    if !input.exists() {
+        eprintln!("manifest line {}: skipped, no {}", line, input.display());
+        return;
+    }
+    run(day, part, input, expected, line);
+}
+
+fn run(day: u32, part: u32, input: PathBuf, expected: &str, line: usize) {
+    let fixture = Fixture {
+        day,
+        part,
+        input,
+        expected: expected.to_string(),
+        line,
+    };
+    if let Err(message) = fixture.check() {
+        panic!("manifest line {}: {}", line, message);
+    }
+}
+
+// one test per line of tests/fixtures/manifest.txt, generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/fixtures.rs"));